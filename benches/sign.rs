@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use jwt::bench;
+use jwt::header::PrecomputedHeader;
+use jwt::{AlgorithmType, Header, SignWithKey, SigningAlgorithm, ToBase64, Token};
+
+fn hs256_sign(c: &mut Criterion) {
+    let key = bench::hs256_key();
+    let claims = bench::claims(3);
+    let token = bench::token(3);
+
+    c.bench_function("hs256 sign claims", |b| {
+        b.iter(|| -> String { black_box(&claims).sign_with_key(&key).unwrap() })
+    });
+
+    c.bench_function("hs256 sign token", |b| {
+        b.iter(|| black_box(&token).sign_with_key(&key).unwrap())
+    });
+
+    // The same key behind a trait object, as loaded for an algorithm chosen
+    // at runtime, to compare dynamic with static dispatch.
+    let boxed_key = jwt::algorithm::from_secret(bench::SECRET, AlgorithmType::Hs256).unwrap();
+    c.bench_function("hs256 sign token boxed key", |b| {
+        b.iter(|| black_box(&token).sign_with_key(&boxed_key).unwrap())
+    });
+
+    let header = PrecomputedHeader::new(Header::default()).unwrap();
+    c.bench_function("hs256 sign token precomputed header", |b| {
+        b.iter(|| {
+            Token::new(black_box(&header), black_box(&claims))
+                .sign_with_key(&key)
+                .unwrap()
+        })
+    });
+
+    // Signing with separately allocated components joined at the end, as a
+    // point of comparison for the single buffer used by `sign_with_key`.
+    c.bench_function("hs256 sign token joined", |b| {
+        b.iter(|| {
+            let header = black_box(&token).header().to_base64().unwrap();
+            let claims = black_box(&token).claims().to_base64().unwrap();
+            let signature = key.sign(&header, &claims).unwrap();
+            [&*header, &*claims, &signature].join(".")
+        })
+    });
+}
+
+#[cfg(feature = "openssl")]
+fn openssl_sign(c: &mut Criterion) {
+    let rs256_key = bench::rs256_private_key();
+    let rs256_token = Token::new(
+        Header {
+            algorithm: AlgorithmType::Rs256,
+            ..Default::default()
+        },
+        bench::claims(3),
+    );
+    c.bench_function("rs256 sign token", |b| {
+        b.iter(|| black_box(&rs256_token).sign_with_key(&rs256_key).unwrap())
+    });
+
+    let rs256_pool = jwt::algorithm::openssl::SignerPool::new(rs256_key).unwrap();
+    c.bench_function("rs256 sign token pooled", |b| {
+        b.iter(|| black_box(&rs256_token).sign_with_key(&rs256_pool).unwrap())
+    });
+
+    let es256_key = bench::es256_private_key();
+    let es256_token = Token::new(
+        Header {
+            algorithm: AlgorithmType::Es256,
+            ..Default::default()
+        },
+        bench::claims(3),
+    );
+    c.bench_function("es256 sign token", |b| {
+        b.iter(|| black_box(&es256_token).sign_with_key(&es256_key).unwrap())
+    });
+}
+
+#[cfg(not(feature = "openssl"))]
+fn openssl_sign(_: &mut Criterion) {}
+
+criterion_group!(benches, hs256_sign, openssl_sign);
+criterion_main!(benches);
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jwt::bench;
+use jwt::{Header, SignWithStore, Store, Token, VerifyWithKey, VerifyWithStore};
+
+type Claims = BTreeMap<String, String>;
+
+fn hs256_verify(c: &mut Criterion) {
+    let key = bench::hs256_key();
+    let token_str = bench::hs256_token(3);
+
+    c.bench_function("hs256 verify claims", |b| {
+        b.iter(|| -> Claims { black_box(token_str.as_str()).verify_with_key(&key).unwrap() })
+    });
+}
+
+fn parse_unverified(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse unverified");
+    for count in [1, 10, 100].iter() {
+        let token_str = bench::hs256_token(*count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &token_str, |b, s| {
+            b.iter(|| {
+                let token: Token<Header, Claims, _> =
+                    Token::parse_unverified(black_box(s)).unwrap();
+                token
+            })
+        });
+    }
+    group.finish();
+}
+
+fn store(c: &mut Criterion) {
+    let store = bench::key_store(1000);
+    let token_str = ("key_500", bench::claims(3))
+        .sign_with_store(&store)
+        .unwrap();
+
+    c.bench_function("store get", |b| {
+        b.iter(|| Store::get(&store, black_box("key_500")).unwrap())
+    });
+
+    c.bench_function("hs256 verify with store", |b| {
+        b.iter(|| -> Claims {
+            black_box(token_str.as_str())
+                .verify_with_store(&store)
+                .unwrap()
+        })
+    });
+}
+
+#[cfg(feature = "openssl")]
+fn openssl_verify(c: &mut Criterion) {
+    use jwt::{AlgorithmType, SignWithKey};
+
+    let rs256_token = Token::new(
+        Header {
+            algorithm: AlgorithmType::Rs256,
+            ..Default::default()
+        },
+        bench::claims(3),
+    )
+    .sign_with_key(&bench::rs256_private_key())
+    .unwrap();
+    let rs256_key = bench::rs256_public_key();
+    c.bench_function("rs256 verify claims", |b| {
+        b.iter(|| -> Claims {
+            black_box(rs256_token.as_str())
+                .verify_with_key(&rs256_key)
+                .unwrap()
+        })
+    });
+
+    let es256_token = Token::new(
+        Header {
+            algorithm: AlgorithmType::Es256,
+            ..Default::default()
+        },
+        bench::claims(3),
+    )
+    .sign_with_key(&bench::es256_private_key())
+    .unwrap();
+    let es256_key = bench::es256_public_key();
+    c.bench_function("es256 verify claims", |b| {
+        b.iter(|| -> Claims {
+            black_box(es256_token.as_str())
+                .verify_with_key(&es256_key)
+                .unwrap()
+        })
+    });
+}
+
+#[cfg(not(feature = "openssl"))]
+fn openssl_verify(_: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    hs256_verify,
+    parse_unverified,
+    store,
+    openssl_verify
+);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(ossl320)");
+
+    // Set by openssl-sys for the OpenSSL it links against, when the openssl
+    // feature is enabled.
+    if let Ok(version) = env::var("DEP_OPENSSL_VERSION_NUMBER") {
+        let version = u64::from_str_radix(&version, 16).unwrap();
+        if version >= 0x3020_0000 {
+            println!("cargo:rustc-cfg=ossl320");
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! A smoke test for `wasm32-unknown-unknown`. Build it with
+//! `cargo build --example wasm --target wasm32-unknown-unknown --features js`
+//! and call `issue_and_check` from JavaScript through wasm-bindgen. It also
+//! runs natively with `cargo run --example wasm`.
+
+use hmac::{Hmac, Mac};
+use jwt::{clock, RegisteredClaims, SignWithKey, VerifyWithKey};
+use sha2::Sha256;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn issue_and_check(secret: &str, subject: &str) -> bool {
+    let key: Hmac<Sha256> = match Hmac::new_from_slice(secret.as_bytes()) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+
+    let claims = RegisteredClaims {
+        subject: Some(subject.into()),
+        issued_at: Some(clock::now()),
+        ..Default::default()
+    };
+
+    let token = match claims.sign_with_key(&key) {
+        Ok(token) => token,
+        Err(_) => return false,
+    };
+
+    let verified: Result<RegisteredClaims, _> = token.as_str().verify_with_key(&key);
+    matches!(verified, Ok(claims) if claims.subject.as_deref() == Some(subject))
+}
+
+fn main() {
+    assert!(issue_and_check("secret_key", "Michael Yang"));
+}
@@ -0,0 +1,313 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_quote, Data, DeriveInput, Error, Field, Fields, Ident, Lit, LitStr, Path, Result};
+
+/// The registered claims, and the fields of `RegisteredClaims` holding them.
+const REGISTERED: [(&str, &str); 7] = [
+    ("iss", "issuer"),
+    ("sub", "subject"),
+    ("aud", "audience"),
+    ("exp", "expiration"),
+    ("nbf", "not_before"),
+    ("iat", "issued_at"),
+    ("jti", "json_web_token_id"),
+];
+
+struct ClaimField<'a> {
+    ident: &'a Ident,
+    /// The name of the claim in the token.
+    name: String,
+    registered: Option<Ident>,
+    required: bool,
+    validate: Option<Path>,
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new(input.span(), "JwtClaims requires named fields")),
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "JwtClaims can only be derived for structs",
+            ))
+        }
+    };
+    let rename_all = rename_all(input)?;
+    let fields = fields
+        .iter()
+        .map(|field| claim_field(field, rename_all.as_deref()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let registered = fields.iter().filter_map(|field| {
+        let ident = field.ident;
+        field.registered.as_ref().map(|registered| {
+            quote! {
+                registered.#registered =
+                    ::jwt::validation::__private::ClaimValue::claim_value(&self.#ident);
+            }
+        })
+    });
+    let required = fields.iter().filter(|field| field.required).map(|field| {
+        let ident = field.ident;
+        let name = &field.name;
+        quote! {
+            if ::core::option::Option::is_none(&self.#ident) {
+                return ::core::result::Result::Err(::jwt::Error::MissingClaim(
+                    ::core::convert::Into::into(#name),
+                ));
+            }
+        }
+    });
+    let validated = fields.iter().filter_map(|field| {
+        let ident = field.ident;
+        field
+            .validate
+            .as_ref()
+            .map(|validate| quote!(#validate(&self.#ident)?;))
+    });
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let mut validator_generics = input.generics.clone();
+    validator_generics
+        .params
+        .push(parse_quote!(__H: ::jwt::JoseHeader));
+    let (validator_impl_generics, _, _) = validator_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::jwt::validation::JwtClaims for #name #type_generics #where_clause {
+            fn registered_claims(&self) -> ::jwt::RegisteredClaims {
+                let mut registered = ::jwt::RegisteredClaims::default();
+                #(#registered)*
+                registered
+            }
+
+            fn validate_fields(&self) -> ::core::result::Result<(), ::jwt::Error> {
+                #(#required)*
+                #(#validated)*
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        ::jwt::__with_clock! {
+            impl #validator_impl_generics ::jwt::validation::ClaimsValidator<#name #type_generics, __H>
+                for ::jwt::validation::Validation #where_clause
+            {
+                fn validate(
+                    &self,
+                    header: &__H,
+                    claims: &#name #type_generics,
+                ) -> ::core::result::Result<(), ::jwt::Error> {
+                    ::jwt::validation::__private::validate(self, header, claims)
+                }
+            }
+        }
+    })
+}
+
+fn claim_field<'a>(field: &'a Field, rename_all: Option<&str>) -> Result<ClaimField<'a>> {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut registered = None;
+    let mut required = false;
+    let mut validate = None;
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("jwt"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("registered") {
+                let name = if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<LitStr>()?
+                } else {
+                    LitStr::new(&ident.to_string(), ident.span())
+                };
+                registered = Some(name);
+                Ok(())
+            } else if meta.path.is_ident("required") {
+                required = true;
+                Ok(())
+            } else if meta.path.is_ident("validate") {
+                validate = Some(meta.value()?.parse::<LitStr>()?.parse::<Path>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `registered`, `required`, or `validate`"))
+            }
+        })?;
+    }
+
+    let renamed = serde_rename(field)?;
+    let default_name = match rename_all {
+        Some(rule) => apply_rename_rule(rule, &ident.unraw().to_string()),
+        None => ident.unraw().to_string(),
+    };
+    let name = match &registered {
+        Some(registered) => {
+            let registered_name = registered.value();
+            let serialized_name = renamed.clone().unwrap_or_else(|| default_name.clone());
+            if serialized_name != registered_name {
+                let message = format!(
+                    "the field must be named `{0}`, or renamed with #[serde(rename = \"{0}\")]",
+                    registered_name
+                );
+                return Err(Error::new(registered.span(), message));
+            }
+            registered_name
+        }
+        None => renamed.unwrap_or(default_name),
+    };
+
+    let registered = match registered {
+        Some(registered) => {
+            let field = REGISTERED
+                .iter()
+                .find(|(claim, _)| *claim == registered.value())
+                .map(|(_, field)| Ident::new(field, Span::call_site()))
+                .ok_or_else(|| Error::new(registered.span(), "unknown registered claim"))?;
+            Some(field)
+        }
+        None => None,
+    };
+
+    Ok(ClaimField {
+        ident,
+        name,
+        registered,
+        required,
+        validate,
+    })
+}
+
+/// The name given to the field with `#[serde(rename = "...")]`.
+fn serde_rename(field: &Field) -> Result<Option<String>> {
+    let mut rename = None;
+    crate::serde_options(&field.attrs, |path, value| {
+        if let (true, Some(Lit::Str(name))) = (path.is_ident("rename"), value) {
+            rename = Some(name.value());
+        }
+    })?;
+    Ok(rename)
+}
+
+/// The rule given to every field with `#[serde(rename_all = "...")]` on the
+/// struct, checked to be one serde knows.
+fn rename_all(input: &DeriveInput) -> Result<Option<String>> {
+    let mut rule = None;
+    crate::serde_options(&input.attrs, |path, value| {
+        if let (true, Some(Lit::Str(value))) = (path.is_ident("rename_all"), value) {
+            rule = Some(value);
+        }
+    })?;
+    match rule {
+        Some(rule) if RENAME_RULES.contains(&rule.value().as_str()) => Ok(Some(rule.value())),
+        Some(rule) => Err(Error::new(rule.span(), "unknown rename rule")),
+        None => Ok(None),
+    }
+}
+
+const RENAME_RULES: [&str; 8] = [
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// The name serde gives a snake case field under `rule`.
+fn apply_rename_rule(rule: &str, field: &str) -> String {
+    let pascal = || {
+        field
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect::<String>()
+    };
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.replace('_', "-").to_ascii_uppercase(),
+        _ => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, DeriveInput};
+
+    use super::{apply_rename_rule, expand};
+
+    #[test]
+    fn rename_rules() {
+        let cases = [
+            ("lowercase", "issued_at"),
+            ("UPPERCASE", "ISSUED_AT"),
+            ("PascalCase", "IssuedAt"),
+            ("camelCase", "issuedAt"),
+            ("snake_case", "issued_at"),
+            ("SCREAMING_SNAKE_CASE", "ISSUED_AT"),
+            ("kebab-case", "issued-at"),
+            ("SCREAMING-KEBAB-CASE", "ISSUED-AT"),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(apply_rename_rule(rule, "issued_at"), expected);
+        }
+    }
+
+    #[test]
+    fn registered_names_after_rename_all() {
+        let renamed: DeriveInput = parse_quote! {
+            #[serde(rename_all = "UPPERCASE")]
+            struct Claims {
+                #[jwt(registered)]
+                exp: u64,
+            }
+        };
+        let error = expand(&renamed).unwrap_err().to_string();
+        assert!(error.starts_with("the field must be named `exp`"));
+
+        let overridden: DeriveInput = parse_quote! {
+            #[serde(rename_all = "UPPERCASE")]
+            struct Claims {
+                #[jwt(registered)]
+                #[serde(rename = "exp")]
+                exp: u64,
+                tenant: String,
+            }
+        };
+        assert!(expand(&overridden).is_ok());
+
+        let unknown: DeriveInput = parse_quote! {
+            #[serde(rename_all = "Title Case")]
+            struct Claims {
+                sub: String,
+            }
+        };
+        assert_eq!(
+            expand(&unknown).unwrap_err().to_string(),
+            "unknown rename rule"
+        );
+    }
+}
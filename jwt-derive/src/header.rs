@@ -0,0 +1,186 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, Member, Result};
+
+/// The fields the methods of `JoseHeader` read.
+#[derive(Default)]
+struct Parameters {
+    algorithm: Option<Member>,
+    key_id: Option<Member>,
+    type_: Option<Member>,
+    content_type: Option<Member>,
+    flatten: Option<Member>,
+    /// Whether the nested header is also flattened by serde, so that it
+    /// writes its parameters next to the fields of the struct.
+    serde_flatten: bool,
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new(input.span(), "JoseHeader requires named fields")),
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "JoseHeader can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut parameters = Parameters::default();
+    for field in fields {
+        let member = Member::Named(field.ident.clone().expect("named field"));
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("jwt"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let parameter = if meta.path.is_ident("alg") {
+                    &mut parameters.algorithm
+                } else if meta.path.is_ident("kid") {
+                    &mut parameters.key_id
+                } else if meta.path.is_ident("typ") {
+                    &mut parameters.type_
+                } else if meta.path.is_ident("cty") {
+                    &mut parameters.content_type
+                } else if meta.path.is_ident("flatten") {
+                    &mut parameters.flatten
+                } else {
+                    return Err(meta.error("expected `alg`, `kid`, `typ`, `cty`, or `flatten`"));
+                };
+                if parameter.is_some() {
+                    return Err(meta.error("the parameter is already mapped to another field"));
+                }
+                *parameter = Some(member.clone());
+                Ok(())
+            })?;
+        }
+        if parameters.flatten.as_ref() == Some(&member) {
+            crate::serde_options(&field.attrs, |path, _| {
+                parameters.serde_flatten |= path.is_ident("flatten");
+            })?;
+        }
+    }
+
+    // A parameter mapped to a field would be written twice, once by the
+    // field and once by the nested header.
+    if parameters.serde_flatten {
+        let mapped = [
+            ("alg", &parameters.algorithm),
+            ("kid", &parameters.key_id),
+            ("typ", &parameters.type_),
+            ("cty", &parameters.content_type),
+        ];
+        if let Some((name, _)) = mapped.iter().find(|(_, member)| member.is_some()) {
+            let message = format!(
+                "`{0}` is also written by the header flattened with #[serde(flatten)]; \
+                 set `{0}` in that header instead",
+                name
+            );
+            return Err(Error::new(input.span(), message));
+        }
+    }
+
+    let algorithm = match (&parameters.algorithm, &parameters.flatten) {
+        (Some(algorithm), _) => quote!(self.#algorithm),
+        (None, Some(flatten)) => quote!(::jwt::JoseHeader::algorithm_type(&self.#flatten)),
+        (None, None) => {
+            return Err(Error::new(
+                input.span(),
+                "JoseHeader requires a field marked #[jwt(alg)] or #[jwt(flatten)]",
+            ))
+        }
+    };
+    let key_id = match (&parameters.key_id, &parameters.flatten) {
+        (Some(key_id), _) => quote!(::jwt::header::__private::KeyIdField::key_id(&self.#key_id)),
+        (None, Some(flatten)) => quote!(::jwt::JoseHeader::key_id(&self.#flatten)),
+        (None, None) => quote!(::core::option::Option::None),
+    };
+    let type_ = optional(&parameters.type_, &parameters.flatten, quote!(type_));
+    let content_type = optional(
+        &parameters.content_type,
+        &parameters.flatten,
+        quote!(content_type),
+    );
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::jwt::JoseHeader for #name #type_generics #where_clause {
+            fn algorithm_type(&self) -> ::jwt::AlgorithmType {
+                #algorithm
+            }
+
+            fn key_id(&self) -> ::core::option::Option<&str> {
+                #key_id
+            }
+
+            fn type_(&self) -> ::core::option::Option<::jwt::header::HeaderType> {
+                #type_
+            }
+
+            fn content_type(&self) -> ::core::option::Option<::jwt::header::HeaderContentType> {
+                #content_type
+            }
+        }
+    })
+}
+
+/// The body of a method returning an optional parameter of the header.
+fn optional(field: &Option<Member>, flatten: &Option<Member>, method: TokenStream) -> TokenStream {
+    match (field, flatten) {
+        (Some(field), _) => {
+            quote!(::jwt::header::__private::HeaderField::header_value(&self.#field))
+        }
+        (None, Some(flatten)) => quote!(::jwt::JoseHeader::#method(&self.#flatten)),
+        (None, None) => quote!(::core::option::Option::None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, DeriveInput};
+
+    use super::expand;
+
+    #[test]
+    fn flattened_parameters() {
+        let nested: DeriveInput = parse_quote! {
+            struct Environment {
+                #[jwt(flatten)]
+                header: Header,
+                #[jwt(kid)]
+                kid: Option<String>,
+            }
+        };
+        assert!(expand(&nested).is_ok());
+
+        let flattened: DeriveInput = parse_quote! {
+            struct Environment {
+                #[serde(flatten)]
+                #[jwt(flatten)]
+                header: Header,
+                environment: String,
+            }
+        };
+        assert!(expand(&flattened).is_ok());
+
+        let duplicated: DeriveInput = parse_quote! {
+            struct Environment {
+                #[serde(default, flatten)]
+                #[jwt(flatten)]
+                header: Header,
+                #[jwt(kid)]
+                #[serde(rename = "kid")]
+                environment_key: Option<String>,
+            }
+        };
+        let error = expand(&duplicated).unwrap_err().to_string();
+        assert!(error.starts_with("`kid` is also written by the header flattened"));
+    }
+}
@@ -0,0 +1,60 @@
+//! Derive macros for the [jwt](https://docs.rs/jwt) crate, re-exported by it
+//! with the `derive` feature. The generated code refers to the jwt crate as
+//! `::jwt`.
+
+extern crate proc_macro;
+
+mod claims;
+mod header;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, Attribute, DeriveInput, Lit, Path, Result};
+
+/// Implement `jwt::validation::JwtClaims` for a struct of claims, and make
+/// `jwt::validation::Validation` a validator of it. See the documentation of
+/// `JwtClaims` in the jwt crate for the attributes.
+#[proc_macro_derive(JwtClaims, attributes(jwt))]
+pub fn derive_jwt_claims(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    claims::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `jwt::JoseHeader` for a header struct, reading the parameters
+/// from the fields marked `#[jwt(alg)]`, `#[jwt(kid)]`, `#[jwt(typ)]`, and
+/// `#[jwt(cty)]`, or else from a nested header marked `#[jwt(flatten)]`. A
+/// nested header also flattened by serde writes its own parameters, so it
+/// must provide all of them.
+#[proc_macro_derive(JoseHeader, attributes(jwt))]
+pub fn derive_jose_header(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    header::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Call `f` with the path and any value of each option of the
+/// `#[serde(...)]` attributes, e.g. `rename = "exp"` or `flatten`. Options
+/// with nested options, such as `rename(serialize = "...")`, are skipped.
+fn serde_options(attrs: &[Attribute], mut f: impl FnMut(&Path, Option<Lit>)) -> Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.input.peek(syn::Token![=]) {
+                let value = meta.value()?.parse::<Lit>()?;
+                f(&meta.path, Some(value));
+            } else if !meta.input.is_empty() && !meta.input.peek(syn::Token![,]) {
+                meta.parse_nested_meta(|nested| {
+                    if nested.input.peek(syn::Token![=]) {
+                        nested.value()?.parse::<Lit>()?;
+                    }
+                    Ok(())
+                })?;
+            } else {
+                f(&meta.path, None);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! The [JWT profile for OAuth 2.0 access tokens](https://tools.ietf.org/html/rfc9068),
+//! for resource servers accepting access tokens from an authorization
+//! server.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::access_token::{AccessTokenClaims, AccessTokenValidation};
+//! use jwt::header::HeaderType;
+//! use jwt::{Header, RegisteredClaims, SignWithKey, Token, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! # use jwt::Error;
+//! # fn try_main() -> Result<(), Error> {
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret")?;
+//! let header = Header {
+//!     type_: Some(HeaderType::AccessToken),
+//!     ..Default::default()
+//! };
+//! let claims = AccessTokenClaims {
+//!     registered: RegisteredClaims {
+//!         issuer: Some("https://auth.example.com".into()),
+//!         subject: Some("someone".into()),
+//!         audience: Some("https://api.example.com".into()),
+//!         expiration: Some(1302319100),
+//!         issued_at: Some(1302318100),
+//!         json_web_token_id: Some("1".into()),
+//!         ..Default::default()
+//!     },
+//!     client_id: Some("app".into()),
+//!     scope: Some("read write".into()),
+//!     ..Default::default()
+//! };
+//! let token_str = Token::new(header, claims).sign_with_key(&key)?;
+//!
+//! let validation = AccessTokenValidation::new("https://auth.example.com", "https://api.example.com");
+//! let token: Token<Header, AccessTokenClaims, _> = token_str.as_str().verify_with_key(&key)?;
+//! validation.validate_at(token.header(), token.claims(), 1302319000)?;
+//! assert!(token.claims().scopes().has_scope("read"));
+//! # Ok(())
+//! # }
+//! # try_main().unwrap()
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::confirmation::Confirmation;
+use crate::error::Error;
+use crate::header::{HeaderType, JoseHeader};
+use crate::scopes::Scopes;
+use crate::validation::Validation;
+
+/// The claims of an RFC 9068 access token. The registered claims `iss`,
+/// `exp`, `aud`, `sub`, `iat` and `jti` are required by the profile. The
+/// `aud` may name several resource servers as an array.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessTokenClaims {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<SecondsSinceEpoch>,
+
+    #[serde(rename = "acr", skip_serializing_if = "Option::is_none")]
+    pub authentication_context_class: Option<String>,
+
+    #[serde(rename = "amr", skip_serializing_if = "Option::is_none")]
+    pub authentication_methods: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entitlements: Option<Vec<String>>,
+
+    #[serde(rename = "cnf", skip_serializing_if = "Option::is_none")]
+    pub confirmation: Option<Confirmation>,
+
+    #[serde(flatten)]
+    pub private: BTreeMap<String, serde_json::Value>,
+}
+
+impl AccessTokenClaims {
+    /// The scopes granted by the `scope` claim.
+    pub fn scopes(&self) -> Scopes {
+        Scopes::parse(self.scope.as_deref().unwrap_or(""))
+    }
+}
+
+impl AsRef<RegisteredClaims> for AccessTokenClaims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+}
+
+/// The checks RFC 9068 requires of a resource server: a `typ` of `at+jwt`,
+/// the expected issuer and audience, an unexpired token, and the presence of
+/// every required claim.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessTokenValidation {
+    validation: Validation,
+}
+
+impl AccessTokenValidation {
+    pub fn new(issuer: impl Into<String>, audience: impl Into<String>) -> Self {
+        let validation = Validation::new()
+            .type_(HeaderType::AccessToken)
+            .issuer(issuer)
+            .audience(audience)
+            .require("exp")
+            .require("sub")
+            .require("iat")
+            .require("jti");
+        AccessTokenValidation { validation }
+    }
+
+    /// Seconds of clock skew to tolerate when checking `exp` and `nbf`.
+    pub fn leeway(mut self, leeway: SecondsSinceEpoch) -> Self {
+        self.validation = self.validation.leeway(leeway);
+        self
+    }
+
+    /// Check the token against the current time of the platform clock.
+    #[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+    pub fn validate(
+        &self,
+        header: &impl JoseHeader,
+        claims: &AccessTokenClaims,
+    ) -> Result<(), Error> {
+        self.validate_at(header, claims, crate::clock::now())
+    }
+
+    /// Check the token against `now`, in seconds since the epoch.
+    pub fn validate_at(
+        &self,
+        header: &impl JoseHeader,
+        claims: &AccessTokenClaims,
+        now: SecondsSinceEpoch,
+    ) -> Result<(), Error> {
+        self.validation.validate_token_at(header, claims, now)?;
+        if claims.client_id.is_none() {
+            return Err(Error::MissingClaim("client_id".into()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+impl<H: JoseHeader> crate::validation::ClaimsValidator<AccessTokenClaims, H>
+    for AccessTokenValidation
+{
+    fn validate(&self, header: &H, claims: &AccessTokenClaims) -> Result<(), Error> {
+        AccessTokenValidation::validate(self, header, claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::access_token::{AccessTokenClaims, AccessTokenValidation};
+    use crate::error::Error;
+    use crate::header::{Header, HeaderType};
+    use crate::FromBase64;
+
+    // {"iss":"https://auth.example.com","sub":"someone","aud":"https://api.example.com",
+    //  "exp":2000,"iat":1000,"jti":"1","client_id":"app","scope":"read write",
+    //  "amr":["pwd","mfa"],"tenant":"mikkyang"}
+    const ENCODED_CLAIMS: &str = "eyJpc3MiOiJodHRwczovL2F1dGguZXhhbXBsZS5jb20iLCJzdWIiOiJzb21lb25lIiwiYXVkIjoiaHR0cHM6Ly9hcGkuZXhhbXBsZS5jb20iLCJleHAiOjIwMDAsImlhdCI6MTAwMCwianRpIjoiMSIsImNsaWVudF9pZCI6ImFwcCIsInNjb3BlIjoicmVhZCB3cml0ZSIsImFtciI6WyJwd2QiLCJtZmEiXSwidGVuYW50IjoibWlra3lhbmcifQ";
+
+    fn header(type_: HeaderType) -> Header {
+        Header {
+            type_: Some(type_),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn parse_claims() -> Result<(), Error> {
+        let claims = AccessTokenClaims::from_base64(ENCODED_CLAIMS)?;
+        assert_eq!(claims.client_id.as_deref(), Some("app"));
+        assert!(claims.scopes().has_all(["read", "write"]));
+        assert_eq!(
+            claims.authentication_methods,
+            Some(vec!["pwd".into(), "mfa".into()])
+        );
+        assert_eq!(claims.private["tenant"], "mikkyang");
+        Ok(())
+    }
+
+    #[test]
+    pub fn validate_profile() -> Result<(), Error> {
+        let mut claims = AccessTokenClaims::from_base64(ENCODED_CLAIMS)?;
+        let validation =
+            AccessTokenValidation::new("https://auth.example.com", "https://api.example.com");
+        validation.validate_at(&header(HeaderType::AccessToken), &claims, 1500)?;
+
+        match validation.validate_at(&header(HeaderType::JsonWebToken), &claims, 1500) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "typ"),
+            other => panic!("Wrong type should not be accepted: {:?}", other),
+        }
+
+        match validation.validate_at(&header(HeaderType::AccessToken), &claims, 2000) {
+            Err(Error::Expired) => (),
+            other => panic!("Expired token should not be accepted: {:?}", other),
+        }
+
+        claims.client_id = None;
+        match validation.validate_at(&header(HeaderType::AccessToken), &claims, 1500) {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "client_id"),
+            other => panic!("Missing client should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn audience_array() -> Result<(), Error> {
+        let mut json: serde_json::Value =
+            serde_json::from_slice(&crate::encoding::decode(ENCODED_CLAIMS)?)?;
+        json["aud"] = serde_json::json!(["https://other.example.com", "https://api.example.com"]);
+        let mut claims: AccessTokenClaims = serde_json::from_value(json)?;
+        let validation =
+            AccessTokenValidation::new("https://auth.example.com", "https://api.example.com");
+        validation.validate_at(&header(HeaderType::AccessToken), &claims, 1500)?;
+
+        claims.registered.audience = Some(vec!["https://other.example.com".into()].into());
+        match validation.validate_at(&header(HeaderType::AccessToken), &claims, 1500) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "aud"),
+            other => panic!("Other audience should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+//! Verification with several keys of different algorithms, e.g. while
+//! migrating from RSA to EC keys. The key is chosen by the `alg` of the token
+//! being verified, so only algorithms that keys were added for are accepted,
+//! and the allowed algorithms can be narrowed further.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::algorithm::AnyVerifier;
+//! use jwt::{AlgorithmType, Header, SignWithKey, Token, VerifyWithKey};
+//! use sha2::{Sha256, Sha512};
+//! use std::collections::BTreeMap;
+//!
+//! let old_key: Hmac<Sha256> = Hmac::new_from_slice(b"old-secret").unwrap();
+//! let new_key: Hmac<Sha512> = Hmac::new_from_slice(b"new-secret").unwrap();
+//! let verifier = AnyVerifier::new().key(old_key.clone()).key(new_key.clone());
+//!
+//! let old_token = BTreeMap::from([("sub", "someone")]).sign_with_key(&old_key).unwrap();
+//! let header = Header { algorithm: AlgorithmType::Hs512, ..Default::default() };
+//! let new_token = Token::new(header, BTreeMap::from([("sub", "someone")]))
+//!     .sign_with_key(&new_key)
+//!     .unwrap();
+//!
+//! let claims: BTreeMap<String, String> = old_token.verify_with_key(&verifier).unwrap();
+//! let claims: BTreeMap<String, String> = new_token.as_str().verify_with_key(&verifier).unwrap();
+//!
+//! // Once every client has migrated, stop accepting the old algorithm.
+//! let verifier = verifier.allow_only(&[AlgorithmType::Hs512]);
+//! let result: Result<BTreeMap<String, String>, _> = old_token.verify_with_key(&verifier);
+//! assert!(result.is_err());
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::Deserialize;
+
+use crate::algorithm::{AlgorithmType, VerifyingAlgorithm};
+use crate::error::Error;
+use crate::FromBase64;
+
+/// A verifier holding keys of several algorithms, which verifies a token
+/// with the keys of the algorithm its header declares. When several keys
+/// share that algorithm, the token is accepted if any of them verifies it.
+/// The keys must be `Send` and `Sync`, so that the verifier is as well.
+#[derive(Default)]
+pub struct AnyVerifier {
+    keys: Vec<Box<dyn VerifyingAlgorithm + Send + Sync>>,
+    allowed: Option<Vec<AlgorithmType>>,
+}
+
+#[derive(Deserialize)]
+struct AlgorithmSelection {
+    #[serde(rename = "alg")]
+    algorithm: AlgorithmType,
+}
+
+impl AnyVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key, allowing its algorithm unless an allow list is set.
+    pub fn key(mut self, key: impl VerifyingAlgorithm + Send + Sync + 'static) -> Self {
+        self.keys.push(Box::new(key));
+        self
+    }
+
+    /// Only accept tokens of `algorithms`, even if keys of other algorithms
+    /// are held.
+    pub fn allow_only(mut self, algorithms: &[AlgorithmType]) -> Self {
+        self.allowed = Some(algorithms.to_vec());
+        self
+    }
+
+    /// The algorithms that tokens are accepted for.
+    pub fn algorithms(&self) -> Vec<AlgorithmType> {
+        let mut algorithms: Vec<AlgorithmType> = Vec::new();
+        for key in &self.keys {
+            let algorithm = key.algorithm_type();
+            if self.accepts(algorithm) && !algorithms.contains(&algorithm) {
+                algorithms.push(algorithm);
+            }
+        }
+        algorithms
+    }
+
+    fn keys_for(&self, algorithm: AlgorithmType) -> impl Iterator<Item = &dyn VerifyingAlgorithm> {
+        self.keys
+            .iter()
+            .map(|key| &**key as &dyn VerifyingAlgorithm)
+            .filter(move |key| key.algorithm_type() == algorithm)
+    }
+}
+
+impl VerifyingAlgorithm for AnyVerifier {
+    /// The algorithm of the first key. Tokens are verified according to
+    /// their own algorithm, as long as it is accepted.
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.keys
+            .first()
+            .map_or(AlgorithmType::None, |key| key.algorithm_type())
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        let AlgorithmSelection { algorithm } = AlgorithmSelection::from_base64(header)?;
+        if !self.accepts(algorithm) {
+            return Err(Error::AlgorithmMismatch(algorithm, self.algorithm_type()));
+        }
+
+        let mut result = Ok(false);
+        for key in self.keys_for(algorithm) {
+            result = key.verify_bytes(header, claims, signature);
+            if let Ok(true) = result {
+                break;
+            }
+        }
+        result
+    }
+
+    fn accepts(&self, algorithm: AlgorithmType) -> bool {
+        let allowed = match &self.allowed {
+            Some(allowed) => allowed.contains(&algorithm),
+            None => true,
+        };
+        allowed && algorithm != AlgorithmType::None && self.keys_for(algorithm).next().is_some()
+    }
+}
+
+impl fmt::Debug for AnyVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnyVerifier")
+            .field("algorithms", &self.algorithms())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use hmac::{Hmac, Mac};
+    use sha2::{Sha256, Sha384};
+
+    use crate::algorithm::{AlgorithmType, AnyVerifier, VerifyingAlgorithm};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::Token;
+
+    fn sign(key: &Hmac<Sha384>) -> Result<String, Error> {
+        let header = Header {
+            algorithm: AlgorithmType::Hs384,
+            ..Default::default()
+        };
+        let claims = BTreeMap::from([("sub", "someone")]);
+        Ok(Token::new(header, claims).sign_with_key(key)?.into())
+    }
+
+    #[test]
+    pub fn dispatch() -> Result<(), Error> {
+        let hs256: Hmac<Sha256> = Hmac::new_from_slice(b"first")?;
+        let rotated: Hmac<Sha384> = Hmac::new_from_slice(b"second")?;
+        let current: Hmac<Sha384> = Hmac::new_from_slice(b"third")?;
+        let verifier = AnyVerifier::new()
+            .key(hs256.clone())
+            .key(rotated.clone())
+            .key(current.clone());
+        assert_eq!(
+            verifier.algorithms(),
+            [AlgorithmType::Hs256, AlgorithmType::Hs384]
+        );
+
+        let claims = BTreeMap::from([("sub", "someone")]);
+        let token_str = claims.sign_with_key(&hs256)?;
+        let _: BTreeMap<String, String> = token_str.as_str().verify_with_key(&verifier)?;
+        let _: BTreeMap<String, String> = sign(&rotated)?.as_str().verify_with_key(&verifier)?;
+        let _: BTreeMap<String, String> = sign(&current)?.as_str().verify_with_key(&verifier)?;
+
+        let unknown: Hmac<Sha384> = Hmac::new_from_slice(b"unknown")?;
+        let result: Result<BTreeMap<String, String>, _> =
+            sign(&unknown)?.as_str().verify_with_key(&verifier);
+        assert!(result.is_err());
+
+        let verifier = verifier.allow_only(&[AlgorithmType::Hs384]);
+        let result: Result<BTreeMap<String, String>, _> =
+            token_str.as_str().verify_with_key(&verifier);
+        match result {
+            Err(Error::AlgorithmMismatch(AlgorithmType::Hs256, _)) => (),
+            other => panic!("Disallowed algorithm should not be accepted: {:?}", other),
+        }
+
+        let hs512_header = "eyJhbGciOiJIUzUxMiJ9";
+        assert!(!verifier.accepts(AlgorithmType::Hs512));
+        assert!(verifier.verify(hs512_header, "e30", "c2ln").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+//! HMAC keys derived from one master secret with
+//! [HKDF](https://tools.ietf.org/html/rfc5869), with the `hkdf` feature. A
+//! service can sign tokens for each purpose or tenant with its own key, while
+//! storing and rotating a single secret. Rotating the master secret rotates
+//! every derived key.
+//! ## Examples
+//! ```
+//! use hmac::Hmac;
+//! use jwt::algorithm::derive_hmac_key;
+//! use jwt::{SignWithKey, VerifyWithKey};
+//! use sha2::Sha256;
+//! use std::collections::BTreeMap;
+//!
+//! let master_secret = b"a master secret of at least 32 bytes";
+//! let key: Hmac<Sha256> = derive_hmac_key(master_secret, b"tenant:mikkyang").unwrap();
+//!
+//! let mut claims = BTreeMap::new();
+//! claims.insert("sub", "someone");
+//! let token_str = claims.sign_with_key(&key).unwrap();
+//!
+//! let other_tenant: Hmac<Sha256> = derive_hmac_key(master_secret, b"tenant:other").unwrap();
+//! let verified: Result<BTreeMap<String, String>, _> = token_str.verify_with_key(&other_tenant);
+//! assert!(verified.is_err());
+//! ```
+//!
+//! With the `std` feature, a [DerivedKeyStore](struct.DerivedKeyStore.html)
+//! derives the key of each tenant from the key id of its tokens on demand, so
+//! that thousands of tenants need no stored secrets.
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::{OnceLock, RwLock};
+
+use digest::{
+    block_buffer::Eager,
+    consts::U256,
+    core_api::{BlockSizeUser, BufferKindUser, CoreProxy, FixedOutputCore, UpdateCore},
+    generic_array::typenum::{IsLess, Le, NonZero},
+    HashMarker, Output, OutputSizeUser,
+};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+
+#[cfg(feature = "std")]
+use crate::algorithm::secret::Secret;
+#[cfg(feature = "std")]
+use crate::algorithm::store::Store;
+use crate::error::Error;
+
+/// Derive the HMAC key for `context`, such as a purpose or tenant id, from
+/// `master_secret`, e.g. `derive_hmac_key::<Sha256>(secret, b"tenant:a")`
+/// for HS256. The key is as long as the hash output, as RFC 7518 requires,
+/// and `context` is used as the HKDF info without a salt, so keys can be
+/// derived the same way by other implementations.
+pub fn derive_hmac_key<D>(master_secret: &[u8], context: &[u8]) -> Result<Hmac<D>, Error>
+where
+    D: CoreProxy + OutputSizeUser,
+    D::Core: HashMarker
+        + UpdateCore
+        + BufferKindUser<BufferKind = Eager>
+        + FixedOutputCore
+        + Default
+        + Clone,
+    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    let hkdf = Hkdf::<D, Hmac<D>>::new(None, master_secret);
+    let mut key = Output::<D>::default();
+    hkdf.expand(context, &mut key).map_err(|_| Error::Format)?;
+    Ok(Hmac::<D>::new_from_slice(&key)?)
+}
+
+#[cfg(feature = "std")]
+type KeyIdFilter = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A store deriving the key of each key id with
+/// [derive_hmac_key](fn.derive_hmac_key.html), using the key id as the
+/// context, and keeping derived keys for later tokens.
+///
+/// Keys are derived before the signature of a token is checked, and the key
+/// id is chosen by whoever sent the token. So only the key ids `filter`
+/// accepts are derived, and at most `capacity` of them. The filter should
+/// only accept the key ids of known tenants, e.g. by looking them up in a
+/// tenant registry. One that accepts any key id lets anyone fill the store
+/// with made-up ones.
+/// ## Capacity
+/// Derived keys are never evicted, since tokens borrow them from the store.
+/// Once `capacity` key ids have been derived, the store is full: the tenants
+/// seen so far are still verified, but the tokens of every other tenant fail
+/// with `Error::NoKeyWithKeyId` until the store is cleared. A full store is
+/// an outage for new tenants, so choose a capacity above the number of
+/// tenants, and watch [is_full](#method.is_full) to clear or replace the
+/// store before it is reached.
+///
+/// Derived keys are borrowed for as long as the store is, so clearing it
+/// takes `&mut self`. A store shared between threads can be put in a
+/// [SharedStore](../store/struct.SharedStore.html) and replaced by an empty
+/// one from [cleared](#method.cleared) instead.
+/// ## Examples
+/// ```
+/// use hmac::Hmac;
+/// use jwt::algorithm::derive_hmac_key;
+/// use jwt::algorithm::derived::DerivedKeyStore;
+/// use jwt::{Header, SignWithKey, Token, VerifyWithStore};
+/// use sha2::Sha256;
+/// use std::collections::BTreeMap;
+///
+/// let master_secret = b"a master secret of at least 32 bytes";
+/// let tenants = ["tenant-7", "tenant-42"];
+/// let store = DerivedKeyStore::<Hmac<Sha256>>::new(
+///     master_secret.to_vec(),
+///     10_000,
+///     move |key_id| tenants.contains(&key_id),
+/// );
+///
+/// // Issued elsewhere with the derived key of the tenant.
+/// let key: Hmac<Sha256> = derive_hmac_key(master_secret, b"tenant-42").unwrap();
+/// let header = Header {
+///     key_id: Some("tenant-42".into()),
+///     ..Default::default()
+/// };
+/// let claims = BTreeMap::from([("sub", "someone")]);
+/// let token = Token::new(header, claims).sign_with_key(&key).unwrap();
+/// let token_str = token.as_str();
+///
+/// let claims: BTreeMap<String, String> = token_str.verify_with_store(&store).unwrap();
+/// assert_eq!(claims["sub"], "someone");
+/// ```
+#[cfg(feature = "std")]
+pub struct DerivedKeyStore<K> {
+    master_secret: Secret,
+    derive: fn(&[u8], &[u8]) -> Result<K, Error>,
+    filter: KeyIdFilter,
+    slots: RwLock<HashMap<String, usize>>,
+    keys: Box<[OnceLock<K>]>,
+}
+
+#[cfg(feature = "std")]
+impl<D> DerivedKeyStore<Hmac<D>>
+where
+    D: CoreProxy + OutputSizeUser,
+    D::Core: HashMarker
+        + UpdateCore
+        + BufferKindUser<BufferKind = Eager>
+        + FixedOutputCore
+        + Default
+        + Clone,
+    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    /// A store deriving keys for at most `capacity` key ids, of those that
+    /// `filter` accepts.
+    pub fn new(
+        master_secret: impl Into<Vec<u8>>,
+        capacity: usize,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        DerivedKeyStore {
+            master_secret: Secret::new(master_secret),
+            derive: derive_hmac_key::<D>,
+            filter: Arc::new(filter),
+            slots: RwLock::new(HashMap::new()),
+            keys: (0..capacity).map(|_| OnceLock::new()).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> DerivedKeyStore<K> {
+    /// The number of keys derived so far.
+    pub fn len(&self) -> usize {
+        self.slots.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `capacity` keys have been derived, so that no other key id
+    /// will be found until the store is cleared.
+    pub fn is_full(&self) -> bool {
+        self.len() == self.keys.len()
+    }
+
+    /// Forget every derived key, e.g. to make room for other key ids.
+    pub fn clear(&mut self) {
+        let capacity = self.keys.len();
+        self.slots = RwLock::new(HashMap::new());
+        self.keys = (0..capacity).map(|_| OnceLock::new()).collect();
+    }
+
+    /// An empty store with the same master secret, filter and capacity.
+    /// ## Examples
+    /// ```
+    /// use hmac::Hmac;
+    /// use jwt::algorithm::derived::DerivedKeyStore;
+    /// use jwt::algorithm::store::SharedStore;
+    /// use sha2::Sha256;
+    ///
+    /// let is_tenant = |key_id: &str| key_id.starts_with("tenant-");
+    /// let store = DerivedKeyStore::<Hmac<Sha256>>::new(b"master secret".to_vec(), 100, is_tenant);
+    /// let shared = SharedStore::new(store);
+    ///
+    /// // Later, before new tenants are locked out.
+    /// let snapshot = shared.snapshot();
+    /// if snapshot.is_full() {
+    ///     shared.replace(snapshot.cleared());
+    /// }
+    /// ```
+    pub fn cleared(&self) -> Self {
+        DerivedKeyStore {
+            master_secret: self.master_secret.clone(),
+            derive: self.derive,
+            filter: Arc::clone(&self.filter),
+            slots: RwLock::new(HashMap::new()),
+            keys: (0..self.keys.len()).map(|_| OnceLock::new()).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> Store for DerivedKeyStore<K> {
+    type Algorithm = K;
+
+    fn get(&self, key_id: &str) -> Option<&K> {
+        if !(self.filter)(key_id) {
+            return None;
+        }
+
+        let slots = self.slots.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(&slot) = slots.get(key_id) {
+            return self.keys[slot].get();
+        }
+        drop(slots);
+
+        // Keys are only ever added to the next free slot, so references to
+        // derived keys stay valid until the store is mutably borrowed.
+        let mut slots = self.slots.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(&slot) = slots.get(key_id) {
+            return self.keys[slot].get();
+        }
+        let slot = slots.len();
+        let cell = self.keys.get(slot)?;
+        let key = (self.derive)(self.master_secret.as_bytes(), key_id.as_bytes()).ok()?;
+        let key = cell.get_or_init(|| key);
+        slots.insert(key_id.into(), slot);
+        Some(key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> fmt::Debug for DerivedKeyStore<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let slots = self.slots.read().unwrap_or_else(|e| e.into_inner());
+        f.debug_struct("DerivedKeyStore")
+            .field("master_secret", &self.master_secret)
+            .field("key_ids", &slots.keys().collect::<Vec<_>>())
+            .field("capacity", &self.keys.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::Hmac;
+    use sha2::{Sha256, Sha512};
+
+    use crate::algorithm::derived::derive_hmac_key;
+    use crate::algorithm::SigningAlgorithm;
+    use crate::error::Error;
+
+    #[test]
+    pub fn derived_keys() -> Result<(), Error> {
+        let master_secret = [0x0b; 22];
+        let sign = |key: Hmac<Sha256>| key.sign("e30", "e30");
+
+        let key = derive_hmac_key(&master_secret, b"a")?;
+        assert_eq!(sign(key)?, sign(derive_hmac_key(&master_secret, b"a")?)?);
+        assert_ne!(
+            sign(derive_hmac_key(&master_secret, b"a")?)?,
+            sign(derive_hmac_key(&master_secret, b"b")?)?
+        );
+        assert_ne!(
+            sign(derive_hmac_key(&master_secret, b"a")?)?,
+            sign(derive_hmac_key(&[0x0c; 22], b"a")?)?
+        );
+
+        // The key is the HKDF output of RFC 5869, test case 3, which has no
+        // salt or info.
+        let expected = crate::algorithm::from_secret(
+            &hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d"),
+            crate::algorithm::AlgorithmType::Hs256,
+        )?;
+        assert_eq!(
+            sign(derive_hmac_key(&master_secret, b"")?)?,
+            expected.sign("e30", "e30")?
+        );
+
+        let key: Hmac<Sha512> = derive_hmac_key(&master_secret, b"a")?;
+        assert_eq!(key.algorithm_type(), crate::algorithm::AlgorithmType::Hs512);
+        Ok(())
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    pub fn derived_key_store() -> Result<(), Error> {
+        use std::collections::BTreeMap;
+
+        use crate::algorithm::derived::DerivedKeyStore;
+        use crate::algorithm::store::Store;
+        use crate::header::Header;
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::VerifyWithStore;
+        use crate::Token;
+
+        let master_secret = b"master secret";
+        let mut store =
+            DerivedKeyStore::<Hmac<Sha256>>::new(&master_secret[..], 2, |kid| kid != "blocked");
+        assert!(store.is_empty());
+
+        let sign = |tenant: &str| -> Result<String, Error> {
+            let key: Hmac<Sha256> = derive_hmac_key(master_secret, tenant.as_bytes())?;
+            let header = Header {
+                key_id: Some(tenant.into()),
+                ..Default::default()
+            };
+            let token = Token::new(header, BTreeMap::from([("sub", "someone")]));
+            Ok(token.sign_with_key(&key)?.into())
+        };
+        for tenant in ["a", "b", "a"] {
+            let token_str = sign(tenant)?;
+            let verified: BTreeMap<String, String> = token_str.verify_with_store(&store)?;
+            assert_eq!(verified["sub"], "someone");
+        }
+        assert_eq!(store.len(), 2);
+
+        let key = store.get("a").unwrap() as *const Hmac<Sha256>;
+        assert_eq!(store.get("a").unwrap() as *const Hmac<Sha256>, key);
+
+        // A full store still verifies the tenants it has, but no others.
+        assert!(store.is_full());
+        assert!(store.get("c").is_none());
+        assert!(store.get("blocked").is_none());
+        let token_str = sign("c")?;
+        let full: Result<BTreeMap<String, String>, _> = token_str.verify_with_store(&store);
+        assert!(matches!(full, Err(Error::NoKeyWithKeyId(kid)) if kid == "c"));
+        let verified: BTreeMap<String, String> = sign("b")?.verify_with_store(&store)?;
+        assert_eq!(verified["sub"], "someone");
+
+        let cleared = store.cleared();
+        assert!(cleared.is_empty());
+        assert!(cleared.get("c").is_some());
+        assert!(cleared.get("blocked").is_none());
+
+        store.clear();
+        assert!(store.is_empty());
+        assert!(!store.is_full());
+        assert!(store.get("c").is_some());
+        assert!(!format!("{:?}", store).contains("master secret"));
+        Ok(())
+    }
+}
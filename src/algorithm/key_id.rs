@@ -0,0 +1,167 @@
+//! Consistent generation of key ids. Choosing a `kid` scheme once, and
+//! deriving every key id from it, keeps the ids a service publishes stable
+//! across restarts and deployments, so tokens signed before a restart can
+//! still be matched to their key.
+//! ## Examples
+//! ```
+//! use jwt::algorithm::key_id::KeyIdStrategy;
+//! use serde_json::json;
+//!
+//! let jwk = json!({
+//!     "kty": "EC",
+//!     "crv": "P-256",
+//!     "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+//!     "y": "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0",
+//! });
+//! let jwk = jwk.as_object().unwrap();
+//!
+//! let key_id = KeyIdStrategy::JwkThumbprint.key_id(jwk).unwrap();
+//! assert_eq!(key_id, "oKIywvGUpTVTyxMQ3bwIIeQUudfr_CkLMjCE19ECD-U");
+//!
+//! let prefixed = KeyIdStrategy::custom(|key| {
+//!     Ok(format!("signing-{}", KeyIdStrategy::JwkThumbprint.key_id(key)?))
+//! });
+//! assert!(prefixed.key_id(jwk).unwrap().starts_with("signing-oKIy"));
+//! ```
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::confirmation::jwk_thumbprint;
+use crate::encoding;
+use crate::error::Error;
+
+/// A key whose public part can be described, for deriving its key id.
+pub trait KeyIdSource {
+    /// The public key as a JWK, with at least the members covered by its
+    /// [RFC 7638](https://tools.ietf.org/html/rfc7638) thumbprint.
+    fn public_jwk(&self) -> Result<Map<String, Value>, Error>;
+
+    /// The DER encoded SubjectPublicKeyInfo of the public key.
+    fn public_key_der(&self) -> Result<Vec<u8>, Error>;
+}
+
+/// A JWK is its own description. Since it has no DER encoding,
+/// `KeyIdStrategy::PublicKeyDer` fails with `Error::Format` for it.
+impl KeyIdSource for Map<String, Value> {
+    fn public_jwk(&self) -> Result<Map<String, Value>, Error> {
+        Ok(self.clone())
+    }
+
+    fn public_key_der(&self) -> Result<Vec<u8>, Error> {
+        Err(Error::Format)
+    }
+}
+
+type CustomKeyId = dyn Fn(&dyn KeyIdSource) -> Result<String, Error> + Send + Sync;
+
+/// How key ids are derived from keys.
+#[derive(Clone)]
+pub enum KeyIdStrategy {
+    /// The RFC 7638 thumbprint of the public key, which any holder of the
+    /// public JWK can recompute.
+    JwkThumbprint,
+    /// The SHA-256 digest of the DER encoded public key, base64 encoded.
+    PublicKeyDer,
+    /// A random version 4 UUID, unrelated to the key, with the `random`
+    /// feature.
+    #[cfg(feature = "random")]
+    Uuid,
+    /// An application defined scheme.
+    Custom(Arc<CustomKeyId>),
+}
+
+impl KeyIdStrategy {
+    pub fn custom(
+        key_id: impl Fn(&dyn KeyIdSource) -> Result<String, Error> + Send + Sync + 'static,
+    ) -> Self {
+        KeyIdStrategy::Custom(Arc::new(key_id))
+    }
+
+    /// The key id of `key`.
+    pub fn key_id(&self, key: &dyn KeyIdSource) -> Result<String, Error> {
+        match self {
+            KeyIdStrategy::JwkThumbprint => jwk_thumbprint(&key.public_jwk()?),
+            KeyIdStrategy::PublicKeyDer => {
+                Ok(encoding::encode(Sha256::digest(key.public_key_der()?)))
+            }
+            #[cfg(feature = "random")]
+            KeyIdStrategy::Uuid => random_uuid(),
+            KeyIdStrategy::Custom(key_id) => key_id(key),
+        }
+    }
+}
+
+impl fmt::Debug for KeyIdStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyIdStrategy::JwkThumbprint => f.write_str("JwkThumbprint"),
+            KeyIdStrategy::PublicKeyDer => f.write_str("PublicKeyDer"),
+            #[cfg(feature = "random")]
+            KeyIdStrategy::Uuid => f.write_str("Uuid"),
+            KeyIdStrategy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+#[cfg(feature = "random")]
+fn random_uuid() -> Result<String, Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        uuid.push_str(&format!("{:02x}", byte));
+    }
+    Ok(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::algorithm::key_id::KeyIdStrategy;
+    use crate::error::Error;
+
+    #[test]
+    pub fn strategies() -> Result<(), Error> {
+        // The example key of RFC 7638.
+        let jwk = json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+        });
+        let jwk = jwk.as_object().unwrap();
+
+        let thumbprint = KeyIdStrategy::JwkThumbprint.key_id(jwk)?;
+        assert_eq!(thumbprint, "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+        assert!(matches!(
+            KeyIdStrategy::PublicKeyDer.key_id(jwk),
+            Err(Error::Format)
+        ));
+
+        #[cfg(feature = "random")]
+        {
+            let first = KeyIdStrategy::Uuid.key_id(jwk)?;
+            assert_eq!(first.len(), 36);
+            assert_eq!(&first[14..15], "4");
+            assert_ne!(first, KeyIdStrategy::Uuid.key_id(jwk)?);
+        }
+
+        let fixed = KeyIdStrategy::custom(|_| Ok("fixed".into()));
+        assert_eq!(fixed.key_id(jwk)?, "fixed");
+        assert_eq!(format!("{:?}", fixed), "Custom(..)");
+        Ok(())
+    }
+}
@@ -2,6 +2,11 @@
 //! `hmac` crate's `Hmac` type is supported. For more algorithms, enable the
 //! feature `openssl` and see the [openssl](openssl/index.html)
 //! module. The `none` algorithm is explicitly not supported.
+//!
+//! A key only verifies tokens whose header names the key's own algorithm, so
+//! a token claiming `HS256` is never checked against an RSA or EC key. HMAC
+//! keys built here refuse PEM encoded secrets, which closes the other half of
+//! the classic `RS256` to `HS256` confusion attack.
 //! ## Examples
 //! ```
 //! use hmac::{Hmac, Mac};
@@ -9,21 +14,55 @@
 //!
 //! let hs256_key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
 //! ```
+//!
+//! When the algorithm is only known at runtime, e.g. when read from
+//! configuration, [from_secret](fn.from_secret.html) picks the matching type.
+//! ```
+//! use jwt::algorithm::{self, AlgorithmType};
+//! use jwt::{SignWithKey, VerifyWithKey};
+//! use std::collections::BTreeMap;
+//!
+//! let key = algorithm::from_secret(b"some-secret", AlgorithmType::Hs384).unwrap();
+//! let mut claims = BTreeMap::new();
+//! claims.insert("sub", "someone");
+//! let token_str = claims.sign_with_key(&key).unwrap();
+//! let claims: BTreeMap<String, String> = token_str.verify_with_key(&key).unwrap();
+//! assert_eq!(claims["sub"], "someone");
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 
+pub mod any;
+#[cfg(feature = "hkdf")]
+pub mod derived;
+pub mod key_id;
 #[cfg(feature = "openssl")]
 pub mod openssl;
 pub mod rust_crypto;
+pub mod secret;
 pub mod store;
 
+pub use self::any::AnyVerifier;
+#[cfg(feature = "hkdf")]
+pub use self::derived::derive_hmac_key;
+#[cfg(feature = "openssl")]
+pub use self::openssl::{
+    signer_from_pem, signer_from_pem_strict, verifier_from_jwk, verifier_from_pem,
+    verifier_from_pem_strict,
+};
+pub use self::rust_crypto::{from_secret, from_secret_strict, hmac_from_jwk, hmac_from_jwk_k};
+
 /// The type of an algorithm, corresponding to the
 /// [JWA](https://tools.ietf.org/html/rfc7518) specification.
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum AlgorithmType {
+    #[default]
     Hs256,
     Hs384,
     Hs512,
@@ -40,29 +79,65 @@ pub enum AlgorithmType {
     None,
 }
 
-impl Default for AlgorithmType {
-    fn default() -> Self {
-        AlgorithmType::Hs256
+/// The modulus length, in bytes, assumed for the signatures of RSA keys when
+/// the key itself is not known.
+const DEFAULT_RSA_SIGNATURE_LEN: usize = 2048 / 8;
+
+impl AlgorithmType {
+    /// The length in bytes of the signatures of the algorithm, before they
+    /// are base64 encoded. RSA signatures are as long as the modulus of the
+    /// key, which is assumed to be 2048 bits.
+    pub fn signature_len(self) -> usize {
+        match self {
+            AlgorithmType::Hs256 => 32,
+            AlgorithmType::Hs384 => 48,
+            AlgorithmType::Hs512 => 64,
+            AlgorithmType::Rs256
+            | AlgorithmType::Rs384
+            | AlgorithmType::Rs512
+            | AlgorithmType::Ps256
+            | AlgorithmType::Ps384
+            | AlgorithmType::Ps512 => DEFAULT_RSA_SIGNATURE_LEN,
+            AlgorithmType::Es256 => 64,
+            AlgorithmType::Es384 => 96,
+            AlgorithmType::Es512 => 132,
+            AlgorithmType::None => 0,
+        }
     }
 }
 
 /// An algorithm capable of signing base64 encoded header and claims strings.
-/// strings.
+/// Every key type of the crate is `Send` and `Sync`, so keys can be shared
+/// between threads and async tasks; boxed keys should be boxed as
+/// `Box<dyn SigningAlgorithm + Send + Sync>` to stay so.
 pub trait SigningAlgorithm {
     fn algorithm_type(&self) -> AlgorithmType;
 
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error>;
+
+    /// The length in bytes of the signatures of this key, before they are
+    /// base64 encoded. By default, that of its algorithm type.
+    fn signature_len(&self) -> usize {
+        self.algorithm_type().signature_len()
+    }
 }
 
 /// An algorithm capable of verifying base64 encoded header and claims strings.
+/// Like signing keys, the verifying keys of the crate are `Send` and `Sync`.
 pub trait VerifyingAlgorithm {
     fn algorithm_type(&self) -> AlgorithmType;
 
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error>;
 
     fn verify(&self, header: &str, claims: &str, signature: &str) -> Result<bool, Error> {
-        let signature_bytes = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)?;
-        self.verify_bytes(header, claims, &*signature_bytes)
+        let signature_bytes = crate::encoding::decode(signature)?;
+        self.verify_bytes(header, claims, &signature_bytes)
+    }
+
+    /// Whether tokens whose header declares `algorithm` may be verified with
+    /// this key. By default, only the key's own algorithm is accepted.
+    fn accepts(&self, algorithm: AlgorithmType) -> bool {
+        algorithm == self.algorithm_type()
     }
 }
 
@@ -75,6 +150,10 @@ impl<T: AsRef<dyn VerifyingAlgorithm>> VerifyingAlgorithm for T {
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
         self.as_ref().verify_bytes(header, claims, signature)
     }
+
+    fn accepts(&self, algorithm: AlgorithmType) -> bool {
+        self.as_ref().accepts(algorithm)
+    }
 }
 
 impl<T: AsRef<dyn SigningAlgorithm>> SigningAlgorithm for T {
@@ -85,4 +164,194 @@ impl<T: AsRef<dyn SigningAlgorithm>> SigningAlgorithm for T {
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
         self.as_ref().sign(header, claims)
     }
+
+    fn signature_len(&self) -> usize {
+        self.as_ref().signature_len()
+    }
+}
+
+/// An algorithm capable of both signing and verifying, such as a symmetric
+/// key. Boxed, it can stand in for either trait when the concrete key type is
+/// only known at runtime. Boxed keys are `Send` and `Sync`, like the key types
+/// they hold.
+pub trait Algorithm: SigningAlgorithm + VerifyingAlgorithm + Send + Sync {}
+
+impl<T: SigningAlgorithm + VerifyingAlgorithm + Send + Sync> Algorithm for T {}
+
+impl SigningAlgorithm for Box<dyn Algorithm> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        SigningAlgorithm::algorithm_type(&**self)
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        (**self).sign(header, claims)
+    }
+
+    fn signature_len(&self) -> usize {
+        (**self).signature_len()
+    }
+}
+
+impl VerifyingAlgorithm for Box<dyn Algorithm> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        VerifyingAlgorithm::algorithm_type(&**self)
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        (**self).verify_bytes(header, claims, signature)
+    }
+
+    fn accepts(&self, algorithm: AlgorithmType) -> bool {
+        (**self).accepts(algorithm)
+    }
+}
+
+impl SigningAlgorithm for Box<dyn SigningAlgorithm + Send + Sync> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        (**self).algorithm_type()
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        (**self).sign(header, claims)
+    }
+
+    fn signature_len(&self) -> usize {
+        (**self).signature_len()
+    }
+}
+
+impl VerifyingAlgorithm for Box<dyn VerifyingAlgorithm + Send + Sync> {
+    fn algorithm_type(&self) -> AlgorithmType {
+        (**self).algorithm_type()
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        (**self).verify_bytes(header, claims, signature)
+    }
+
+    fn accepts(&self, algorithm: AlgorithmType) -> bool {
+        (**self).accepts(algorithm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use std::collections::BTreeMap;
+
+    use hmac::Hmac;
+    use sha2::{Sha256, Sha384, Sha512};
+
+    use crate::algorithm::key_id::KeyIdStrategy;
+    use crate::algorithm::secret::Secret;
+    use crate::algorithm::store::{InMemoryStore, PinnedStore};
+    use crate::algorithm::{Algorithm, AnyVerifier, SigningAlgorithm, VerifyingAlgorithm};
+    use crate::token::issuer::MultiIssuerVerifier;
+    use crate::validation::Validation;
+
+    type Keys = BTreeMap<String, Box<dyn VerifyingAlgorithm + Send + Sync>>;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    /// Keys and stores are held in shared application state and moved into
+    /// spawned tasks, so the crate's own types must stay
+    /// `Send + Sync + 'static`. Implementations of the algorithm traits
+    /// outside the crate need not be.
+    #[test]
+    pub fn send_sync() {
+        assert_send_sync::<Hmac<Sha256>>();
+        assert_send_sync::<Hmac<Sha384>>();
+        assert_send_sync::<Hmac<Sha512>>();
+        assert_send_sync::<Box<dyn Algorithm>>();
+        assert_send_sync::<Box<dyn SigningAlgorithm + Send + Sync>>();
+        assert_send_sync::<Box<dyn VerifyingAlgorithm + Send + Sync>>();
+        assert_send_sync::<AnyVerifier>();
+        assert_send_sync::<Secret>();
+        assert_send_sync::<KeyIdStrategy>();
+        assert_send_sync::<Validation>();
+
+        assert_send_sync::<InMemoryStore<Box<dyn Algorithm>>>();
+        assert_send_sync::<PinnedStore<Box<dyn Algorithm>>>();
+        assert_send_sync::<MultiIssuerVerifier<Keys>>();
+        #[cfg(feature = "std")]
+        {
+            assert_send_sync::<crate::algorithm::store::SharedStore<Keys>>();
+            assert_send_sync::<crate::algorithm::secret::HmacKeyFromEnv>();
+            assert_send_sync::<crate::cache::LruCache<String>>();
+            assert_send_sync::<crate::cache::FailureTracker>();
+        }
+        #[cfg(feature = "hkdf")]
+        assert_send_sync::<crate::algorithm::derived::DerivedKeyStore<Hmac<Sha256>>>();
+    }
+
+    /// Boxed keys that are `Send` and `Sync` can be used as keys themselves,
+    /// and moved to another thread.
+    #[test]
+    pub fn send_sync_boxes() -> Result<(), crate::error::Error> {
+        use hmac::Mac;
+
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::VerifyWithKey;
+
+        let signer: Box<dyn SigningAlgorithm + Send + Sync> =
+            Box::new(Hmac::<Sha256>::new_from_slice(b"secret")?);
+        let verifier: Box<dyn VerifyingAlgorithm + Send + Sync> =
+            Box::new(Hmac::<Sha256>::new_from_slice(b"secret")?);
+        let token_str =
+            std::thread::spawn(move || BTreeMap::from([("sub", "someone")]).sign_with_key(&signer))
+                .join()
+                .unwrap()?;
+
+        let mut keys: Keys = BTreeMap::new();
+        keys.insert("key".into(), verifier);
+        let claims: BTreeMap<String, String> =
+            std::thread::spawn(move || token_str.verify_with_key(&keys["key"]))
+                .join()
+                .unwrap()?;
+        assert_eq!(claims["sub"], "someone");
+        Ok(())
+    }
+
+    /// Keys outside the crate may hold state that stays on one thread.
+    #[test]
+    pub fn local_keys() -> Result<(), crate::error::Error> {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        use hmac::Mac;
+
+        use crate::algorithm::AlgorithmType;
+        use crate::token::signed::SignWithKey;
+
+        struct CountingKey(Rc<Cell<u32>>, Hmac<Sha256>);
+
+        impl SigningAlgorithm for CountingKey {
+            fn algorithm_type(&self) -> AlgorithmType {
+                SigningAlgorithm::algorithm_type(&self.1)
+            }
+
+            fn sign(&self, header: &str, claims: &str) -> Result<String, crate::error::Error> {
+                self.0.set(self.0.get() + 1);
+                self.1.sign(header, claims)
+            }
+        }
+
+        let signatures = Rc::new(Cell::new(0));
+        let key = CountingKey(Rc::clone(&signatures), Hmac::new_from_slice(b"secret")?);
+        BTreeMap::from([("sub", "someone")]).sign_with_key(&key)?;
+        assert_eq!(signatures.get(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    pub fn openssl_send_sync() {
+        use crate::algorithm::openssl::{DeterministicEcdsa, PKeyWithDigest, SignerPool};
+        use openssl::pkey::{Private, Public};
+
+        assert_send_sync::<PKeyWithDigest<Private>>();
+        assert_send_sync::<PKeyWithDigest<Public>>();
+        assert_send_sync::<DeterministicEcdsa>();
+        assert_send_sync::<SignerPool>();
+    }
 }
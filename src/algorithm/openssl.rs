@@ -12,21 +12,87 @@
 //!     key: PKey::public_key_from_pem(pem).unwrap(),
 //! };
 //! ```
+//!
+//! Keys can also be loaded for an algorithm chosen at runtime, with the key
+//! type and digest picked from the algorithm.
+//! ```
+//! use jwt::algorithm::{signer_from_pem, verifier_from_pem, AlgorithmType};
+//! let private_pem = include_bytes!("../../test/es256-private.pem");
+//! let public_pem = include_bytes!("../../test/es256-public.pem");
+//! let signer = signer_from_pem(private_pem, AlgorithmType::Es256).unwrap();
+//! let verifier = verifier_from_pem(public_pem, AlgorithmType::Es256).unwrap();
+//! ```
+//!
+//! The `_strict` variants additionally reject RSA keys shorter than
+//! [MIN_RSA_BITS](constant.MIN_RSA_BITS.html) and EC keys on a different curve
+//! than the algorithm's, with `Error::WeakKey`.
+//!
+//! RSA signing uses OpenSSL's blinded private key operations, which protect
+//! the key against timing attacks.
+//!
+//! ECDSA signatures use random nonces. Wrap an EC key in
+//! [DeterministicEcdsa](struct.DeterministicEcdsa.html) for reproducible
+//! signatures instead. Verification only accepts the signature form JWS
+//! requires, unless the public key is wrapped in
+//! [LenientEcdsa](struct.LenientEcdsa.html) to accept DER as well.
+//!
+//! Every key type here is `Send` and `Sync`, so a key can be shared in an
+//! `Arc` by the tasks of a server. Signing with a `PKeyWithDigest` sets up a
+//! new OpenSSL context for every token, and a
+//! [SignerPool](struct.SignerPool.html) reuses them instead.
+
+use alloc::boxed::Box;
+use core::fmt;
+use std::sync::Mutex;
+
+use serde_json::{Map, Value};
 
+use crate::algorithm::key_id::KeyIdSource;
 use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::encoding;
 use crate::error::Error;
 use crate::SEPARATOR;
 
-use openssl::bn::BigNum;
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{EcGroup, EcKey};
 use openssl::ecdsa::EcdsaSig;
 use openssl::hash::MessageDigest;
+use openssl::md::{Md, MdRef};
+use openssl::md_ctx::MdCtx;
 use openssl::nid::Nid;
-use openssl::pkey::{Id, PKey, Private, Public};
+use openssl::pkey::{HasPublic, Id, PKey, Private, Public};
+use openssl::rsa::Rsa;
 use openssl::sign::{Signer, Verifier};
 
 /// A wrapper class around [PKey](../../../openssl/pkey/struct.PKey.html) that
 /// associates the key with a
 /// [MessageDigest](../../../openssl/hash/struct.MessageDigest.html).
+///
+/// Only `PKeyWithDigest<Private>` signs and only `PKeyWithDigest<Public>`
+/// verifies, so using a key for the wrong purpose fails to compile. The
+/// verifier of a private key is its [public_key](#method.public_key).
+/// ```compile_fail
+/// use jwt::{PKeyWithDigest, VerifyWithKey};
+/// use openssl::hash::MessageDigest;
+/// use openssl::pkey::PKey;
+/// let pem = include_bytes!("../../test/rs256-private.pem");
+/// let private_key = PKeyWithDigest {
+///     digest: MessageDigest::sha256(),
+///     key: PKey::private_key_from_pem(pem).unwrap(),
+/// };
+/// let claims: String = "a.b.c".verify_with_key(&private_key).unwrap();
+/// ```
+/// ```compile_fail
+/// use jwt::{PKeyWithDigest, SignWithKey};
+/// use openssl::hash::MessageDigest;
+/// use openssl::pkey::PKey;
+/// let pem = include_bytes!("../../test/rs256-public.pem");
+/// let public_key = PKeyWithDigest {
+///     digest: MessageDigest::sha256(),
+///     key: PKey::public_key_from_pem(pem).unwrap(),
+/// };
+/// let token = "claims".sign_with_key(&public_key).unwrap();
+/// ```
 pub struct PKeyWithDigest<T> {
     pub digest: MessageDigest,
     pub key: PKey<T>,
@@ -46,25 +112,239 @@ impl<T> PKeyWithDigest<T> {
     }
 }
 
+impl<T: HasPublic> PKeyWithDigest<T> {
+    /// Fail with `Error::WeakKey` if an RSA key has a modulus shorter than
+    /// [MIN_RSA_BITS](constant.MIN_RSA_BITS.html), or if an EC key is not on
+    /// the curve its algorithm is defined for.
+    pub fn check_strength(&self) -> Result<(), Error> {
+        let algorithm = PKeyWithDigest::algorithm_type(self);
+        let strong = match self.key.id() {
+            Id::RSA => self.key.bits() >= MIN_RSA_BITS,
+            Id::EC => {
+                let curve = match algorithm {
+                    AlgorithmType::Es256 => Nid::X9_62_PRIME256V1,
+                    AlgorithmType::Es384 => Nid::SECP384R1,
+                    _ => Nid::SECP521R1,
+                };
+                self.key.ec_key()?.group().curve_name() == Some(curve)
+            }
+            _ => true,
+        };
+
+        if strong {
+            Ok(())
+        } else {
+            Err(Error::WeakKey(algorithm))
+        }
+    }
+}
+
+impl PKeyWithDigest<Private> {
+    /// The public half of the key, with the same digest, for verifying the
+    /// tokens this key signs.
+    pub fn public_key(&self) -> Result<PKeyWithDigest<Public>, Error> {
+        Ok(PKeyWithDigest {
+            digest: self.digest,
+            key: PKey::public_key_from_der(&self.key.public_key_to_der()?)?,
+        })
+    }
+
+    /// The signature in the form JWS uses, from the one OpenSSL produced.
+    fn jose_signature(&self, signature: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if self.key.id() == Id::EC {
+            let degree = self.key.ec_key()?.group().degree();
+            der_to_jose(&signature, degree.div_ceil(8) as i32)
+        } else {
+            Ok(signature)
+        }
+    }
+}
+
+/// The public key as a JWK, and its DER encoding, for deriving key ids.
+impl<T: HasPublic> KeyIdSource for PKeyWithDigest<T> {
+    fn public_jwk(&self) -> Result<Map<String, Value>, Error> {
+        let mut jwk = Map::new();
+        match self.key.id() {
+            Id::RSA => {
+                let rsa = self.key.rsa()?;
+                jwk.insert("kty".into(), "RSA".into());
+                jwk.insert("n".into(), encoding::encode(rsa.n().to_vec()).into());
+                jwk.insert("e".into(), encoding::encode(rsa.e().to_vec()).into());
+            }
+            Id::EC => {
+                let ec = self.key.ec_key()?;
+                let group = ec.group();
+                let curve = match group.curve_name() {
+                    Some(Nid::X9_62_PRIME256V1) => "P-256",
+                    Some(Nid::SECP384R1) => "P-384",
+                    Some(Nid::SECP521R1) => "P-521",
+                    _ => return Err(Error::UnsupportedAlgorithm(self.algorithm_type())),
+                };
+
+                let mut x = BigNum::new()?;
+                let mut y = BigNum::new()?;
+                let mut context = BigNumContext::new()?;
+                ec.public_key()
+                    .affine_coordinates(group, &mut x, &mut y, &mut context)?;
+                let length = (group.degree() as usize).div_ceil(8);
+
+                jwk.insert("kty".into(), "EC".into());
+                jwk.insert("crv".into(), curve.into());
+                jwk.insert("x".into(), coordinate(&x, length)?.into());
+                jwk.insert("y".into(), coordinate(&y, length)?.into());
+            }
+            _ => return Err(Error::UnsupportedAlgorithm(self.algorithm_type())),
+        }
+        Ok(jwk)
+    }
+
+    fn public_key_der(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.key.public_key_to_der()?)
+    }
+}
+
+/// An EC coordinate, padded to the length of the curve's field elements.
+fn coordinate(value: &BigNumRef, length: usize) -> Result<String, Error> {
+    Ok(encoding::encode(value.to_vec_padded(length as i32)?))
+}
+
 impl SigningAlgorithm for PKeyWithDigest<Private> {
     fn algorithm_type(&self) -> AlgorithmType {
         PKeyWithDigest::algorithm_type(self)
     }
 
     fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
-        let mut signer = Signer::new(self.digest.clone(), &self.key)?;
+        let mut signer = Signer::new(self.digest, &self.key)?;
         signer.update(header.as_bytes())?;
         signer.update(SEPARATOR.as_bytes())?;
         signer.update(claims.as_bytes())?;
-        let signer_signature = signer.sign_to_vec()?;
+        let signature = self.jose_signature(signer.sign_to_vec()?)?;
 
-        let signature = if self.key.id() == Id::EC {
-            der_to_jose(&signer_signature)?
-        } else {
-            signer_signature
+        Ok(encoding::encode(signature))
+    }
+
+    /// The length of the modulus of RSA keys, or twice that of the field
+    /// elements of the curve of EC keys.
+    fn signature_len(&self) -> usize {
+        match self.key.id() {
+            Id::RSA => self.key.size(),
+            Id::EC => match self.key.ec_key() {
+                Ok(ec) => 2 * ec.group().degree().div_ceil(8) as usize,
+                Err(_) => self.algorithm_type().signature_len(),
+            },
+            _ => self.algorithm_type().signature_len(),
+        }
+    }
+}
+
+/// A private key that keeps the OpenSSL contexts it signs with for the next
+/// tokens, instead of allocating one per token like `PKeyWithDigest`. It can
+/// be shared between threads, which each take a context from the pool for
+/// the duration of a signature, so the pool grows to the number of tokens
+/// signed at once and no further.
+/// ## Examples
+/// ```
+/// use jwt::algorithm::openssl::SignerPool;
+/// use jwt::{AlgorithmType, Header, PKeyWithDigest, SignWithKey, Token};
+/// use openssl::hash::MessageDigest;
+/// use openssl::pkey::PKey;
+/// use std::collections::BTreeMap;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let pem = include_bytes!("../../test/rs256-private.pem");
+/// let key = PKeyWithDigest {
+///     digest: MessageDigest::sha256(),
+///     key: PKey::private_key_from_pem(pem).unwrap(),
+/// };
+/// let key = Arc::new(SignerPool::new(key).unwrap());
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let key = Arc::clone(&key);
+///         thread::spawn(move || {
+///             let header = Header { algorithm: AlgorithmType::Rs256, ..Default::default() };
+///             let claims = BTreeMap::from([("sub", "someone")]);
+///             Token::new(header, claims).sign_with_key(&*key).unwrap();
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// ```
+pub struct SignerPool {
+    key: PKeyWithDigest<Private>,
+    md: &'static MdRef,
+    contexts: Mutex<Vec<MdCtx>>,
+}
+
+impl SignerPool {
+    /// Pool signing contexts for `key`. No context is created until the first
+    /// signature, which reuses an idle context or creates another. Fails with
+    /// `Error::UnsupportedAlgorithm` if OpenSSL has no digest for the digest
+    /// of `key`.
+    pub fn new(key: PKeyWithDigest<Private>) -> Result<Self, Error> {
+        let md = Md::from_nid(key.digest.type_())
+            .ok_or_else(|| Error::UnsupportedAlgorithm(key.algorithm_type()))?;
+        Ok(SignerPool {
+            key,
+            md,
+            contexts: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The key of the pool. The pooled contexts are dropped.
+    pub fn into_inner(self) -> PKeyWithDigest<Private> {
+        self.key
+    }
+
+    fn sign_message(&self, context: &mut MdCtx, message: &[u8]) -> Result<Vec<u8>, Error> {
+        context.digest_sign_init(Some(self.md), &self.key.key)?;
+        let mut signature = Vec::new();
+        context.digest_sign_to_vec(message, &mut signature)?;
+        Ok(signature)
+    }
+
+    fn contexts(&self) -> std::sync::MutexGuard<'_, Vec<MdCtx>> {
+        // The contexts are reset before they are returned, so a panic while
+        // holding the lock cannot leave a used one in the pool.
+        self.contexts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SigningAlgorithm for SignerPool {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.key.algorithm_type()
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        let pooled = self.contexts().pop();
+        let mut context = match pooled {
+            Some(context) => context,
+            None => MdCtx::new()?,
         };
 
-        Ok(base64::encode_config(&signature, base64::URL_SAFE_NO_PAD))
+        let message = [header, SEPARATOR, claims].concat();
+        let signature = self.sign_message(&mut context, message.as_bytes());
+        if context.reset().is_ok() {
+            self.contexts().push(context);
+        }
+
+        Ok(encoding::encode(self.key.jose_signature(signature?)?))
+    }
+
+    fn signature_len(&self) -> usize {
+        self.key.signature_len()
+    }
+}
+
+impl fmt::Debug for SignerPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SignerPool")
+            .field("algorithm", &self.key.algorithm_type())
+            .field("contexts", &self.contexts().len())
+            .finish()
     }
 }
 
@@ -74,27 +354,279 @@ impl VerifyingAlgorithm for PKeyWithDigest<Public> {
     }
 
     fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
-        let mut verifier = Verifier::new(self.digest.clone(), &self.key)?;
+        if self.key.id() == Id::EC {
+            self.verify_openssl(header, claims, &jose_to_der(signature)?)
+        } else {
+            self.verify_openssl(header, claims, signature)
+        }
+    }
+}
+
+impl PKeyWithDigest<Public> {
+    /// Verify a signature in the form OpenSSL produces, DER for ECDSA.
+    fn verify_openssl(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        let mut verifier = Verifier::new(self.digest, &self.key)?;
         verifier.update(header.as_bytes())?;
         verifier.update(SEPARATOR.as_bytes())?;
         verifier.update(claims.as_bytes())?;
+        Ok(verifier.verify(signature)?)
+    }
+}
+
+/// An EC public key that also accepts ECDSA signatures in DER form, as some
+/// non-compliant issuers produce, besides the concatenated (R, S) form JWS
+/// requires. A signature that is a canonical DER encoding of a signature is
+/// taken as DER. Accepting both forms does not weaken verification, since
+/// either is checked against the key, but it should only be used for issuers
+/// known to need it: `PKeyWithDigest` accepts only the JWS form.
+/// ## Examples
+/// ```
+/// use jwt::algorithm::openssl::{LenientEcdsa, PKeyWithDigest};
+/// use openssl::hash::MessageDigest;
+/// use openssl::pkey::PKey;
+///
+/// let pem = include_bytes!("../../test/es256-public.pem");
+/// let key = PKeyWithDigest {
+///     digest: MessageDigest::sha256(),
+///     key: PKey::public_key_from_pem(pem).unwrap(),
+/// };
+/// let verifier = LenientEcdsa::new(key).unwrap();
+/// ```
+pub struct LenientEcdsa(PKeyWithDigest<Public>);
+
+impl LenientEcdsa {
+    /// Fails with `Error::UnsupportedAlgorithm` if the key is not an EC key.
+    pub fn new(key: PKeyWithDigest<Public>) -> Result<Self, Error> {
+        if key.key.id() != Id::EC {
+            return Err(Error::UnsupportedAlgorithm(key.algorithm_type()));
+        }
+        Ok(LenientEcdsa(key))
+    }
 
-        let verified = if self.key.id() == Id::EC {
-            let der = jose_to_der(signature)?;
-            verifier.verify(&der)?
+    pub fn into_inner(self) -> PKeyWithDigest<Public> {
+        self.0
+    }
+}
+
+impl VerifyingAlgorithm for LenientEcdsa {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.0.algorithm_type()
+    }
+
+    fn verify_bytes(&self, header: &str, claims: &str, signature: &[u8]) -> Result<bool, Error> {
+        if is_der_signature(signature) {
+            self.0.verify_openssl(header, claims, signature)
         } else {
-            verifier.verify(signature)?
-        };
+            self.0.verify_bytes(header, claims, signature)
+        }
+    }
+}
+
+/// Whether `signature` is the canonical DER encoding of an ECDSA signature,
+/// a sequence of two integers.
+fn is_der_signature(signature: &[u8]) -> bool {
+    signature.first() == Some(&0x30)
+        && EcdsaSig::from_der(signature)
+            .and_then(|parsed| parsed.to_der())
+            .is_ok_and(|der| der == signature)
+}
 
-        Ok(verified)
+/// An EC private key that signs with deterministic nonces, as described in
+/// [RFC 6979](https://tools.ietf.org/html/rfc6979), so that signing the same
+/// token with the same key always produces the same signature. This makes
+/// signatures reproducible for snapshot tests and for comparing the output
+/// of different implementations.
+///
+/// Deterministic signatures are as secure as randomized ones, and do not
+/// depend on a good source of randomness when signing. However, they reveal
+/// whether two tokens are identical, and make fault attacks on the signing
+/// device easier, which is why `PKeyWithDigest` keeps signing with random
+/// nonces. Signatures of both kinds are verified the same way.
+///
+/// Requires OpenSSL 3.2 or newer. With older versions, `new` fails with
+/// `Error::UnsupportedAlgorithm`.
+pub struct DeterministicEcdsa(PKeyWithDigest<Private>);
+
+impl DeterministicEcdsa {
+    pub fn new(key: PKeyWithDigest<Private>) -> Result<Self, Error> {
+        if key.key.id() != Id::EC || cfg!(not(ossl320)) {
+            return Err(Error::UnsupportedAlgorithm(key.algorithm_type()));
+        }
+        Ok(DeterministicEcdsa(key))
+    }
+
+    pub fn into_inner(self) -> PKeyWithDigest<Private> {
+        self.0
+    }
+
+    #[cfg(ossl320)]
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        use openssl::md::{Md, MdRef};
+        use openssl::pkey_ctx::{NonceType, PkeyCtx};
+
+        // The nonce is derived from the digest, so it is signed directly
+        // instead of through a Signer, which does not expose the nonce type.
+        let md = Md::from_nid(self.0.digest.type_())
+            .ok_or_else(|| Error::UnsupportedAlgorithm(self.0.algorithm_type()))?;
+        let digest = openssl::hash::hash(self.0.digest, message)?;
+        let mut context = PkeyCtx::new(&self.0.key)?;
+        context.sign_init()?;
+        context.set_signature_md(md)?;
+        context.set_nonce_type(NonceType::DETERMINISTIC_K)?;
+        let mut signature = Vec::new();
+        context.sign_to_vec(&digest, &mut signature)?;
+        self.0.jose_signature(signature)
+    }
+
+    #[cfg(not(ossl320))]
+    fn sign_message(&self, _: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::UnsupportedAlgorithm(self.0.algorithm_type()))
     }
 }
 
-/// OpenSSL by default signs ECDSA in DER, but JOSE expects them in a concatenated (R, S) format
-fn der_to_jose(der: &[u8]) -> Result<Vec<u8>, Error> {
-    let signature = EcdsaSig::from_der(&der)?;
-    let r = signature.r().to_vec();
-    let s = signature.s().to_vec();
+impl SigningAlgorithm for DeterministicEcdsa {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.0.algorithm_type()
+    }
+
+    fn sign(&self, header: &str, claims: &str) -> Result<String, Error> {
+        let message = [header, SEPARATOR, claims].concat();
+        Ok(encoding::encode(self.sign_message(message.as_bytes())?))
+    }
+
+    fn signature_len(&self) -> usize {
+        self.0.signature_len()
+    }
+}
+
+/// The shortest RSA modulus, in bits, accepted by the strict key
+/// constructors, as required by RFC 7518.
+pub const MIN_RSA_BITS: u32 = 2048;
+
+/// The key type and digest used by `algorithm`. RSASSA-PSS is not supported.
+fn key_parameters(algorithm: AlgorithmType) -> Result<(Id, MessageDigest), Error> {
+    use AlgorithmType::*;
+
+    match algorithm {
+        Rs256 => Ok((Id::RSA, MessageDigest::sha256())),
+        Rs384 => Ok((Id::RSA, MessageDigest::sha384())),
+        Rs512 => Ok((Id::RSA, MessageDigest::sha512())),
+        Es256 => Ok((Id::EC, MessageDigest::sha256())),
+        Es384 => Ok((Id::EC, MessageDigest::sha384())),
+        Es512 => Ok((Id::EC, MessageDigest::sha512())),
+        other => Err(Error::UnsupportedAlgorithm(other)),
+    }
+}
+
+fn with_digest<T>(key: PKey<T>, algorithm: AlgorithmType) -> Result<PKeyWithDigest<T>, Error> {
+    let (id, digest) = key_parameters(algorithm)?;
+    if key.id() != id {
+        return Err(Error::UnsupportedAlgorithm(algorithm));
+    }
+    Ok(PKeyWithDigest { digest, key })
+}
+
+pub(crate) fn private_key_from_pem(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<PKeyWithDigest<Private>, Error> {
+    with_digest(PKey::private_key_from_pem(pem)?, algorithm)
+}
+
+fn public_key_from_pem(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<PKeyWithDigest<Public>, Error> {
+    let key = match PKey::public_key_from_pem(pem) {
+        Ok(key) => key,
+        Err(_) => return private_key_from_pem(pem, algorithm)?.public_key(),
+    };
+    with_digest(key, algorithm)
+}
+
+/// Load a PEM encoded RSA or EC private key for signing with `algorithm`.
+/// The key must be of the type the algorithm requires.
+pub fn signer_from_pem(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn SigningAlgorithm + Send + Sync>, Error> {
+    Ok(Box::new(private_key_from_pem(pem, algorithm)?))
+}
+
+/// Like [signer_from_pem](fn.signer_from_pem.html), but also check the
+/// strength of the key.
+pub fn signer_from_pem_strict(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn SigningAlgorithm + Send + Sync>, Error> {
+    let key = private_key_from_pem(pem, algorithm)?;
+    key.check_strength()?;
+    Ok(Box::new(key))
+}
+
+/// Load a PEM encoded RSA or EC key for verifying with `algorithm`. A private
+/// key is accepted as well, in which case only its public half is kept.
+pub fn verifier_from_pem(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    Ok(Box::new(public_key_from_pem(pem, algorithm)?))
+}
+
+/// Like [verifier_from_pem](fn.verifier_from_pem.html), but also check the
+/// strength of the key.
+pub fn verifier_from_pem_strict(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    let key = public_key_from_pem(pem, algorithm)?;
+    key.check_strength()?;
+    Ok(Box::new(key))
+}
+
+fn public_key_from_jwk(
+    jwk: &Map<String, Value>,
+    algorithm: AlgorithmType,
+) -> Result<PKeyWithDigest<Public>, Error> {
+    let member = |name: &str| -> Result<BigNum, Error> {
+        let value = jwk.get(name).and_then(Value::as_str).ok_or(Error::Format)?;
+        Ok(BigNum::from_slice(&encoding::decode(value)?)?)
+    };
+
+    let key = match jwk.get("kty").and_then(Value::as_str) {
+        Some("RSA") => PKey::from_rsa(Rsa::from_public_components(member("n")?, member("e")?)?)?,
+        Some("EC") => {
+            let curve = match jwk.get("crv").and_then(Value::as_str) {
+                Some("P-256") => Nid::X9_62_PRIME256V1,
+                Some("P-384") => Nid::SECP384R1,
+                Some("P-521") => Nid::SECP521R1,
+                _ => return Err(Error::UnsupportedAlgorithm(algorithm)),
+            };
+            let group = EcGroup::from_curve_name(curve)?;
+            let (x, y) = (member("x")?, member("y")?);
+            let ec = EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+            PKey::from_ec_key(ec)?
+        }
+        _ => return Err(Error::UnsupportedAlgorithm(algorithm)),
+    };
+    with_digest(key, algorithm)
+}
+
+/// Load an RSA or EC public key given as a JWK for verifying with
+/// `algorithm`. The key must be of the type the algorithm requires.
+pub fn verifier_from_jwk(
+    jwk: &Map<String, Value>,
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    Ok(Box::new(public_key_from_jwk(jwk, algorithm)?))
+}
+
+/// OpenSSL by default signs ECDSA in DER, but JOSE expects them in a concatenated (R, S) format,
+/// each padded to the `length` of the curve's coordinates
+fn der_to_jose(der: &[u8], length: i32) -> Result<Vec<u8>, Error> {
+    let signature = EcdsaSig::from_der(der)?;
+    let r = signature.r().to_vec_padded(length)?;
+    let s = signature.s().to_vec_padded(length)?;
     Ok([r, s].concat())
 }
 
@@ -108,7 +640,10 @@ fn jose_to_der(jose: &[u8]) -> Result<Vec<u8>, Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::algorithm::openssl::PKeyWithDigest;
+    use crate::algorithm::openssl::{
+        signer_from_pem, signer_from_pem_strict, verifier_from_pem, verifier_from_pem_strict,
+        PKeyWithDigest,
+    };
     use crate::algorithm::AlgorithmType::*;
     use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
     use crate::error::Error;
@@ -119,10 +654,9 @@ mod tests {
     use openssl::pkey::PKey;
 
     // {"sub":"1234567890","name":"John Doe","admin":true}
-    const CLAIMS: &'static str =
-        "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+    const CLAIMS: &str = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
 
-    const RS256_SIGNATURE: &'static str =
+    const RS256_SIGNATURE: &str =
     "cQsAHF2jHvPGFP5zTD8BgoJrnzEx6JNQCpupebWLFnOc2r_punDDTylI6Ia4JZNkvy2dQP-7W-DEbFQ3oaarHsDndqUgwf9iYlDQxz4Rr2nEZX1FX0-FMEgFPeQpdwveCgjtTYUbVy37ijUySN_rW-xZTrsh_Ug-ica8t-zHRIw";
 
     #[test]
@@ -136,6 +670,7 @@ mod tests {
 
         let result = algorithm.sign(&AlgOnly(Rs256).to_base64()?, CLAIMS)?;
         assert_eq!(result, RS256_SIGNATURE);
+        assert_eq!(algorithm.signature_len(), 1024 / 8);
         Ok(())
     }
 
@@ -163,6 +698,8 @@ mod tests {
         };
 
         let signature = private_key.sign(&AlgOnly(Es256).to_base64()?, CLAIMS)?;
+        assert_eq!(signature.len(), 86);
+        assert_eq!(private_key.signature_len(), Es256.signature_len());
 
         let public_pem = include_bytes!("../../test/es256-public.pem");
 
@@ -172,8 +709,322 @@ mod tests {
         };
 
         let verification_result =
-            public_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &*signature)?;
+            public_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &signature)?;
         assert!(verification_result);
+
+        let derived_key = private_key.public_key()?;
+        assert_eq!(
+            derived_key.key.public_key_to_der()?,
+            public_key.key.public_key_to_der()?
+        );
+        assert!(derived_key.verify(&AlgOnly(Es256).to_base64()?, CLAIMS, &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn der_to_jose_pads_components() -> Result<(), Error> {
+        use crate::algorithm::openssl::{der_to_jose, jose_to_der};
+        use openssl::bn::BigNum;
+        use openssl::ecdsa::EcdsaSig;
+
+        // An R with a leading zero byte encodes to fewer than 32 bytes in DER.
+        let r = BigNum::from_slice(&[0x7f; 31])?;
+        let s = BigNum::from_slice(&[0x80; 32])?;
+        let der = EcdsaSig::from_private_components(r, s)?.to_der()?;
+
+        let jose = der_to_jose(&der, 32)?;
+        assert_eq!(jose.len(), 64);
+        assert_eq!(jose[0], 0);
+        assert_eq!(&jose[1..32], &[0x7f; 31][..]);
+        assert_eq!(&jose[32..], &[0x80; 32][..]);
+        assert_eq!(jose_to_der(&jose)?, der);
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_es256() -> Result<(), Error> {
+        use crate::algorithm::openssl::LenientEcdsa;
+        use openssl::sign::Signer;
+
+        let private_key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../../test/es256-private.pem"))?,
+        };
+        let header = AlgOnly(Es256).to_base64()?;
+        let jose_signature = private_key.sign(&header, CLAIMS)?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &private_key.key)?;
+        let der = signer.sign_oneshot_to_vec(format!("{}.{}", header, CLAIMS).as_bytes())?;
+        let der_signature = crate::encoding::encode(&der);
+
+        let strict = private_key.public_key()?;
+        assert!(!strict
+            .verify(&header, CLAIMS, &der_signature)
+            .unwrap_or(false));
+
+        let lenient = LenientEcdsa::new(private_key.public_key()?)?;
+        assert!(lenient.verify(&header, CLAIMS, &der_signature)?);
+        assert!(lenient.verify(&header, CLAIMS, &jose_signature)?);
+        assert!(!lenient.verify(&AlgOnly(Es256).to_base64()?, "e30", &der_signature)?);
+
+        let rsa = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::public_key_from_pem(include_bytes!("../../test/rs256-public.pem"))?,
+        };
+        match LenientEcdsa::new(rsa) {
+            Err(Error::UnsupportedAlgorithm(Rs256)) => (),
+            other => panic!("RSA key should not be accepted: {:?}", other.is_ok()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signer_pool() -> Result<(), Error> {
+        use crate::algorithm::openssl::SignerPool;
+        use std::sync::Arc;
+        use std::thread;
+
+        let pem = include_bytes!("../../test/rs256-private.pem");
+        let pool = Arc::new(SignerPool::new(super::private_key_from_pem(pem, Rs256)?)?);
+        let header = AlgOnly(Rs256).to_base64()?.into_owned();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                let header = header.clone();
+                thread::spawn(move || -> Result<(), Error> {
+                    for _ in 0..4 {
+                        assert_eq!(pool.sign(&header, CLAIMS)?, RS256_SIGNATURE);
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("signing thread panicked")?;
+        }
+        assert!(pool.contexts().len() <= 4);
+
+        let private_pem = include_bytes!("../../test/es256-private.pem");
+        let public_pem = include_bytes!("../../test/es256-public.pem");
+        let pool = SignerPool::new(super::private_key_from_pem(private_pem, Es256)?)?;
+        let header = AlgOnly(Es256).to_base64()?;
+        let verifier = verifier_from_pem(public_pem, Es256)?;
+        for _ in 0..2 {
+            let signature = pool.sign(&header, CLAIMS)?;
+            assert!(verifier.verify(&header, CLAIMS, &signature)?);
+        }
+        assert_eq!(pool.contexts().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn deterministic_es256() -> Result<(), Error> {
+        use crate::algorithm::openssl::DeterministicEcdsa;
+
+        let private_pem = include_bytes!("../../test/es256-private.pem");
+        let public_pem = include_bytes!("../../test/es256-public.pem");
+        let header = AlgOnly(Es256).to_base64()?;
+        let key = super::private_key_from_pem(private_pem, Es256)?;
+
+        if cfg!(not(ossl320)) {
+            assert!(DeterministicEcdsa::new(key).is_err());
+            return Ok(());
+        }
+
+        let signer = DeterministicEcdsa::new(key)?;
+        let signature = signer.sign(&header, CLAIMS)?;
+        assert_eq!(signature, signer.sign(&header, CLAIMS)?);
+        assert_ne!(signature, signer.sign(&header, "e30")?);
+        let verifier = verifier_from_pem(public_pem, Es256)?;
+        assert!(verifier.verify(&header, CLAIMS, &signature)?);
+
+        let rsa =
+            super::private_key_from_pem(include_bytes!("../../test/rs256-private.pem"), Rs256)?;
+        assert!(matches!(
+            DeterministicEcdsa::new(rsa),
+            Err(Error::UnsupportedAlgorithm(Rs256))
+        ));
+        Ok(())
+    }
+
+    /// The P-256 and SHA-256 test vector of RFC 6979, appendix A.2.5.
+    #[cfg(ossl320)]
+    #[test]
+    fn rfc6979_vector() -> Result<(), Error> {
+        use crate::algorithm::openssl::DeterministicEcdsa;
+        use openssl::bn::{BigNum, BigNumContext};
+        use openssl::ec::{EcGroup, EcKey, EcPoint};
+        use openssl::nid::Nid;
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let private = BigNum::from_hex_str(
+            "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721",
+        )?;
+        let mut context = BigNumContext::new()?;
+        let mut public = EcPoint::new(&group)?;
+        public.mul_generator2(&group, &private, &mut context)?;
+        let key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::from_ec_key(EcKey::from_private_components(&group, &private, &public)?)?,
+        };
+
+        let signature = DeterministicEcdsa::new(key)?.sign_message(b"sample")?;
+        let (r, s) = signature.split_at(32);
+        assert_eq!(
+            BigNum::from_slice(r)?.to_hex_str()?.to_string(),
+            "EFD48B2AACB6A8FD1140DD9CD45E81D69D2C877B56AAF991C34D0EA84EAF3716"
+        );
+        assert_eq!(
+            BigNum::from_slice(s)?.to_hex_str()?.to_string(),
+            "F7CB1C942D657C41D436C7A1B6E29F65F3E900DBB9AFF4064DC4AB2F843ACDA8"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_pem() -> Result<(), Error> {
+        let private_pem = include_bytes!("../../test/rs256-private.pem");
+        let public_pem = include_bytes!("../../test/rs256-public.pem");
+        let header = AlgOnly(Rs256).to_base64()?;
+
+        let signer = signer_from_pem(private_pem, Rs256)?;
+        assert_eq!(signer.algorithm_type(), Rs256);
+        assert_eq!(signer.sign(&header, CLAIMS)?, RS256_SIGNATURE);
+
+        let verifier = verifier_from_pem(public_pem, Rs256)?;
+        assert!(verifier.verify(&header, CLAIMS, RS256_SIGNATURE)?);
+        let verifier = verifier_from_pem(private_pem, Rs256)?;
+        assert!(verifier.verify(&header, CLAIMS, RS256_SIGNATURE)?);
+
+        let es256_pem = include_bytes!("../../test/es256-private.pem");
+        for (pem, algorithm) in [
+            (&private_pem[..], Es256),
+            (es256_pem, Rs256),
+            (private_pem, Ps256),
+        ] {
+            match signer_from_pem(pem, algorithm) {
+                Err(Error::UnsupportedAlgorithm(a)) => assert_eq!(a, algorithm),
+                other => panic!("Expected an unsupported algorithm: {:?}", other.is_ok()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strict() -> Result<(), Error> {
+        let rs256_pem = include_bytes!("../../test/rs256-public.pem");
+        let es256_private_pem = include_bytes!("../../test/es256-private.pem");
+        let es256_public_pem = include_bytes!("../../test/es256-public.pem");
+
+        signer_from_pem_strict(es256_private_pem, Es256)?;
+        verifier_from_pem_strict(es256_public_pem, Es256)?;
+
+        // The test RSA keys only have a 1024 bit modulus.
+        assert!(verifier_from_pem(rs256_pem, Rs256).is_ok());
+        let failures = [
+            verifier_from_pem_strict(rs256_pem, Rs256).map(|_| ()),
+            verifier_from_pem_strict(es256_public_pem, Es384).map(|_| ()),
+            signer_from_pem_strict(es256_private_pem, Es512).map(|_| ()),
+        ];
+        for (result, algorithm) in failures.iter().zip([Rs256, Es384, Es512]) {
+            match result {
+                Err(Error::WeakKey(a)) => assert_eq!(*a, algorithm),
+                other => panic!("Expected a weak key: {:?}", other),
+            }
+        }
+
+        let rsa = openssl::rsa::Rsa::generate(2048)?;
+        let key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::from_rsa(rsa)?,
+        };
+        key.check_strength()?;
+        Ok(())
+    }
+
+    #[test]
+    fn algorithm_confusion() -> Result<(), Error> {
+        use crate::algorithm::store::PinnedStore;
+        use crate::header::Header;
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::{VerifyWithKey, VerifyWithStore};
+        use crate::Token;
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        // An attacker signs a HS256 token using the RSA public key as the
+        // HMAC secret, hoping the verifier will do the same.
+        let public_pem = include_bytes!("../../test/rs256-public.pem");
+        let forging_key: Hmac<Sha256> = Hmac::new_from_slice(public_pem)?;
+        let header = Header {
+            algorithm: Hs256,
+            key_id: Some("rsa".into()),
+            ..Default::default()
+        };
+        let forged = Token::new(header, "admin").sign_with_key(&forging_key)?;
+
+        let verifier = verifier_from_pem(public_pem, Rs256)?;
+        let result: Result<Token<Header, String, _>, _> =
+            forged.as_str().verify_with_key(&verifier);
+        match result {
+            Err(Error::AlgorithmMismatch(Hs256, Rs256)) => (),
+            other => panic!("Forged token should not be accepted: {:?}", other),
+        }
+
+        let mut store = PinnedStore::new();
+        store.insert("rsa", Rs256, verifier);
+        let result: Result<Token<Header, String, _>, _> = forged.as_str().verify_with_store(&store);
+        match result {
+            Err(Error::NoKeyWithKeyId(kid)) => assert_eq!(kid, "rsa"),
+            other => panic!("Forged token should not be accepted: {:?}", other),
+        }
+
+        match crate::algorithm::from_secret(public_pem, Hs256) {
+            Err(Error::WeakKey(Hs256)) => (),
+            other => panic!(
+                "A public key should not be an HMAC secret: {:?}",
+                other.is_ok()
+            ),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn key_ids() -> Result<(), Error> {
+        use crate::algorithm::key_id::{KeyIdSource, KeyIdStrategy};
+        use crate::algorithm::store::InMemoryStore;
+
+        for (private_pem, public_pem, algorithm) in [
+            (
+                &include_bytes!("../../test/es256-private.pem")[..],
+                &include_bytes!("../../test/es256-public.pem")[..],
+                Es256,
+            ),
+            (
+                &include_bytes!("../../test/rs256-private.pem")[..],
+                &include_bytes!("../../test/rs256-public.pem")[..],
+                Rs256,
+            ),
+        ] {
+            let private = super::private_key_from_pem(private_pem, algorithm)?;
+            let mut public = super::public_key_from_pem(public_pem, algorithm)?;
+            assert_eq!(private.public_jwk()?, public.public_jwk()?);
+
+            let from_jwk = super::public_key_from_jwk(&public.public_jwk()?, algorithm)?;
+            assert_eq!(from_jwk.public_key_der()?, public.public_key_der()?);
+            assert!(matches!(
+                super::public_key_from_jwk(&public.public_jwk()?, Hs256),
+                Err(Error::UnsupportedAlgorithm(Hs256))
+            ));
+
+            for strategy in [KeyIdStrategy::JwkThumbprint, KeyIdStrategy::PublicKeyDer] {
+                let mut store = InMemoryStore::new();
+                let key_id = store.insert_with_key_id(&strategy, public)?;
+                assert_eq!(key_id, strategy.key_id(&private)?);
+                assert!(store.contains(&key_id));
+                public = store.remove(&key_id).unwrap();
+            }
+        }
         Ok(())
     }
 }
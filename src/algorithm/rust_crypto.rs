@@ -2,6 +2,9 @@
 //! According to that organization, only hmac is safely implemented at the
 //! moment.
 
+use alloc::boxed::Box;
+use alloc::string::String;
+
 use digest::{
     block_buffer::Eager,
     consts::U256,
@@ -10,8 +13,12 @@ use digest::{
     HashMarker,
 };
 use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::{Sha256, Sha384, Sha512};
 
-use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::algorithm::secret::{check_key_length, is_pem_encoded};
+use crate::algorithm::{Algorithm, AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::encoding;
 use crate::error::Error;
 use crate::SEPARATOR;
 /// A trait used to make the implementation of `SigningAlgorithm` and
@@ -32,9 +39,65 @@ macro_rules! type_level_algorithm_type {
     };
 }
 
-type_level_algorithm_type!(sha2::Sha256, AlgorithmType::Hs256);
-type_level_algorithm_type!(sha2::Sha384, AlgorithmType::Hs384);
-type_level_algorithm_type!(sha2::Sha512, AlgorithmType::Hs512);
+type_level_algorithm_type!(Sha256, AlgorithmType::Hs256);
+type_level_algorithm_type!(Sha384, AlgorithmType::Hs384);
+type_level_algorithm_type!(Sha512, AlgorithmType::Hs512);
+
+/// Create the HMAC key for `algorithm` from a shared secret. Only the `HS*`
+/// algorithms are symmetric, any other algorithm is unsupported. A PEM
+/// encoded key is rejected with `Error::WeakKey`, since it is most likely a
+/// public key that anyone could use to forge tokens.
+pub fn from_secret(secret: &[u8], algorithm: AlgorithmType) -> Result<Box<dyn Algorithm>, Error> {
+    Ok(match algorithm {
+        AlgorithmType::Hs256 | AlgorithmType::Hs384 | AlgorithmType::Hs512
+            if is_pem_encoded(secret) =>
+        {
+            return Err(Error::WeakKey(algorithm))
+        }
+        AlgorithmType::Hs256 => Box::new(Hmac::<Sha256>::new_from_slice(secret)?),
+        AlgorithmType::Hs384 => Box::new(Hmac::<Sha384>::new_from_slice(secret)?),
+        AlgorithmType::Hs512 => Box::new(Hmac::<Sha512>::new_from_slice(secret)?),
+        other => return Err(Error::UnsupportedAlgorithm(other)),
+    })
+}
+
+/// Like [from_secret](fn.from_secret.html), but fail with `Error::WeakKey`
+/// if the secret is shorter than the hash output, as RFC 7518 requires.
+pub fn from_secret_strict(
+    secret: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn Algorithm>, Error> {
+    check_key_length(secret, algorithm)?;
+    from_secret(secret, algorithm)
+}
+
+/// Create an HMAC key from the base64url encoded `k` member of an `oct` JWK,
+/// e.g. `hmac_from_jwk_k::<Sha256>(k)` for HS256.
+pub fn hmac_from_jwk_k<D>(k: &str) -> Result<Hmac<D>, Error>
+where
+    D: CoreProxy,
+    D::Core: HashMarker + BufferKindUser<BufferKind = Eager> + FixedOutputCore + Default + Clone,
+    <D::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<D::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    Ok(Hmac::<D>::new_from_slice(&encoding::decode(k)?)?)
+}
+
+/// Create the HMAC key for `algorithm` from an `oct` JWK, as
+/// [from_secret_strict](fn.from_secret_strict.html) does from its decoded `k`
+/// member. Fails with `Error::Format` if the member is missing, with
+/// `Error::WeakKey` if it is shorter than the hash output, and with
+/// `Error::UnsupportedAlgorithm` for keys of any other type.
+pub fn hmac_from_jwk(
+    jwk: &Map<String, Value>,
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn Algorithm>, Error> {
+    if jwk.get("kty").and_then(Value::as_str) != Some("oct") {
+        return Err(Error::UnsupportedAlgorithm(algorithm));
+    }
+    let k = jwk.get("k").and_then(Value::as_str).ok_or(Error::Format)?;
+    from_secret_strict(&encoding::decode(k)?, algorithm)
+}
 
 impl<D> SigningAlgorithm for Hmac<D>
 where
@@ -56,7 +119,7 @@ where
         let hmac = get_hmac_with_data(self, header, claims);
         let mac_result = hmac.finalize();
         let code = mac_result.into_bytes();
-        Ok(base64::encode_config(&code, base64::URL_SAFE_NO_PAD))
+        Ok(encoding::encode(code))
     }
 }
 
@@ -105,7 +168,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
     use crate::error::Error;
     use hmac::{Hmac, Mac};
     use sha2::Sha256;
@@ -135,4 +198,71 @@ mod tests {
         )?);
         Ok(())
     }
+
+    #[test]
+    pub fn from_secret() -> Result<(), Error> {
+        let header = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+        let claims = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+        let signature = "TJVA95OrM7E2cBab30RMHrHDcEfxjoYZgeFONFh7HgQ";
+
+        let key = super::from_secret(b"secret", AlgorithmType::Hs256)?;
+        assert_eq!(SigningAlgorithm::algorithm_type(&key), AlgorithmType::Hs256);
+        assert_eq!(key.sign(header, claims)?, signature);
+        assert!(key.verify(header, claims, signature)?);
+
+        let key = super::from_secret(b"secret", AlgorithmType::Hs512)?;
+        assert_eq!(
+            VerifyingAlgorithm::algorithm_type(&key),
+            AlgorithmType::Hs512
+        );
+
+        match super::from_secret(b"secret", AlgorithmType::Rs256) {
+            Err(Error::UnsupportedAlgorithm(AlgorithmType::Rs256)) => (),
+            other => panic!("Expected an unsupported algorithm: {:?}", other.is_ok()),
+        }
+
+        assert!(super::from_secret_strict(&[0; 32], AlgorithmType::Hs256).is_ok());
+        match super::from_secret_strict(b"secret", AlgorithmType::Hs256) {
+            Err(Error::WeakKey(AlgorithmType::Hs256)) => (),
+            other => panic!("Expected a weak key: {:?}", other.is_ok()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn from_jwk() -> Result<(), Error> {
+        let header = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+        let claims = "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiYWRtaW4iOnRydWV9";
+        let signature = "TJVA95OrM7E2cBab30RMHrHDcEfxjoYZgeFONFh7HgQ";
+
+        let key: Hmac<Sha256> = super::hmac_from_jwk_k("c2VjcmV0")?;
+        assert_eq!(SigningAlgorithm::sign(&key, header, claims)?, signature);
+
+        let jwk =
+            serde_json::json!({"kty": "oct", "k": "YS1zZWNyZXQtd2hpY2gtaXMtMzItYnl0ZXMtbG9uZyE"});
+        let key = super::hmac_from_jwk(jwk.as_object().unwrap(), AlgorithmType::Hs256)?;
+        let expected: Hmac<Sha256> =
+            super::hmac_from_jwk_k("YS1zZWNyZXQtd2hpY2gtaXMtMzItYnl0ZXMtbG9uZyE")?;
+        let signature = SigningAlgorithm::sign(&expected, header, claims)?;
+        assert!(key.verify(header, claims, &signature)?);
+
+        let jwk = serde_json::json!({"kty": "oct", "k": "c2VjcmV0"});
+        match super::hmac_from_jwk(jwk.as_object().unwrap(), AlgorithmType::Hs256) {
+            Err(Error::WeakKey(AlgorithmType::Hs256)) => (),
+            other => panic!("Expected a weak key: {:?}", other.is_ok()),
+        }
+
+        assert!(super::hmac_from_jwk_k::<Sha256>("not base64!").is_err());
+        let jwk = serde_json::json!({"kty": "RSA", "k": "c2VjcmV0"});
+        match super::hmac_from_jwk(jwk.as_object().unwrap(), AlgorithmType::Hs256) {
+            Err(Error::UnsupportedAlgorithm(AlgorithmType::Hs256)) => (),
+            other => panic!("Expected an unsupported algorithm: {:?}", other.is_ok()),
+        }
+        let jwk = serde_json::json!({"kty": "oct"});
+        match super::hmac_from_jwk(jwk.as_object().unwrap(), AlgorithmType::Hs256) {
+            Err(Error::Format) => (),
+            other => panic!("Expected a format error: {:?}", other.is_ok()),
+        }
+        Ok(())
+    }
 }
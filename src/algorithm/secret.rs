@@ -0,0 +1,311 @@
+//! Raw key material for symmetric algorithms. With the `zeroize` feature the
+//! bytes are wiped from memory when the secret is dropped.
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crypto_common::KeyInit;
+
+#[cfg(feature = "std")]
+use crate::algorithm::Algorithm;
+use crate::algorithm::{AlgorithmType, SigningAlgorithm};
+use crate::encoding::{Engine, Tolerant};
+use crate::error::Error;
+
+/// Secret key bytes, e.g. for constructing an `Hmac`. The `Debug`
+/// implementation never prints the bytes.
+/// ## Examples
+/// ```
+/// use hmac::Hmac;
+/// use jwt::algorithm::secret::Secret;
+/// use sha2::Sha256;
+///
+/// let secret = Secret::new(b"some-secret".to_vec());
+/// let key: Hmac<Sha256> = secret.to_key().unwrap();
+/// assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(Vec<u8>);
+
+impl Secret {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Secret(bytes.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Decode a secret written as text, e.g. in an environment variable. A
+    /// `base64:` prefix marks base64 in either alphabet, with or without
+    /// padding, and a `hex:` prefix marks hex encoded bytes. Text without
+    /// either prefix is used as is.
+    pub fn decode(encoded: &str) -> Result<Self, Error> {
+        if let Some(base64) = encoded.strip_prefix("base64:") {
+            Ok(Secret(Tolerant.decode(base64.as_bytes())?))
+        } else if let Some(hex) = encoded.strip_prefix("hex:") {
+            decode_hex(hex).map(Secret)
+        } else {
+            Ok(Secret(encoded.as_bytes().to_vec()))
+        }
+    }
+
+    /// Construct a key, such as `Hmac<Sha256>`, from the secret.
+    pub fn to_key<K: KeyInit>(&self) -> Result<K, Error> {
+        Ok(K::new_from_slice(&self.0)?)
+    }
+
+    /// Construct a key like [to_key](#method.to_key), but fail with
+    /// `Error::WeakKey` if the secret is too short for the key's algorithm,
+    /// or is a PEM encoded key.
+    pub fn to_strict_key<K: KeyInit + SigningAlgorithm>(&self) -> Result<K, Error> {
+        let key: K = self.to_key()?;
+        let algorithm = key.algorithm_type();
+        if is_pem_encoded(&self.0) {
+            return Err(Error::WeakKey(algorithm));
+        }
+        self.check_length(algorithm)?;
+        Ok(key)
+    }
+
+    /// Fail if the secret is shorter than `algorithm` requires, see
+    /// [min_key_length](fn.min_key_length.html).
+    pub fn check_length(&self, algorithm: AlgorithmType) -> Result<(), Error> {
+        check_key_length(&self.0, algorithm)
+    }
+}
+
+/// Fail with `Error::WeakKey` if `secret` is shorter than `algorithm`
+/// requires, see [min_key_length](fn.min_key_length.html).
+pub fn check_key_length(secret: &[u8], algorithm: AlgorithmType) -> Result<(), Error> {
+    match min_key_length(algorithm) {
+        Some(min) if secret.len() < min => Err(Error::WeakKey(algorithm)),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `secret` is a PEM encoded key. These are most likely the public
+/// half of an RSA or EC key, so an HMAC key made from one could be used by
+/// anyone to forge tokens: the classic algorithm confusion attack.
+pub(crate) fn is_pem_encoded(secret: &[u8]) -> bool {
+    let start = secret
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(secret.len());
+    secret[start..].starts_with(b"-----BEGIN ")
+}
+
+/// The shortest secret allowed for an HMAC algorithm, which RFC 7518 sets to
+/// the size of the hash output. Other algorithms have no minimum here.
+pub fn min_key_length(algorithm: AlgorithmType) -> Option<usize> {
+    match algorithm {
+        AlgorithmType::Hs256 => Some(32),
+        AlgorithmType::Hs384 => Some(48),
+        AlgorithmType::Hs512 => Some(64),
+        _ => None,
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    fn nibble(digit: u8) -> Result<u8, Error> {
+        match (digit as char).to_digit(16) {
+            Some(value) => Ok(value as u8),
+            None => Err(Error::Format),
+        }
+    }
+
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::Format);
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+        .collect()
+}
+
+/// An HMAC secret read from an environment variable, decoded as in
+/// [Secret::decode](struct.Secret.html#method.decode). Secrets shorter than
+/// the algorithm's hash output are rejected.
+/// ## Examples
+/// ```
+/// use hmac::Hmac;
+/// use jwt::algorithm::secret::HmacKeyFromEnv;
+/// use sha2::Sha256;
+///
+/// std::env::set_var("EXAMPLE_HMAC_SECRET", format!("hex:{}", "ab".repeat(32)));
+/// let key: Hmac<Sha256> = HmacKeyFromEnv::new("EXAMPLE_HMAC_SECRET").key().unwrap();
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HmacKeyFromEnv {
+    name: String,
+}
+
+#[cfg(feature = "std")]
+impl HmacKeyFromEnv {
+    pub fn new(name: impl Into<String>) -> Self {
+        HmacKeyFromEnv { name: name.into() }
+    }
+
+    /// Read and decode the secret, without checking its length.
+    pub fn secret(&self) -> Result<Secret, Error> {
+        let encoded = std::env::var(&self.name).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                alloc::format!("{}: {}", self.name, e),
+            )
+        })?;
+        Secret::decode(&encoded)
+    }
+
+    /// Construct the key type, such as `Hmac<Sha256>`.
+    pub fn key<K: KeyInit + SigningAlgorithm>(&self) -> Result<K, Error> {
+        self.secret()?.to_strict_key()
+    }
+
+    /// Construct the key for an algorithm only known at runtime.
+    pub fn key_for(&self, algorithm: AlgorithmType) -> Result<Box<dyn Algorithm>, Error> {
+        crate::algorithm::from_secret_strict(self.secret()?.as_bytes(), algorithm)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+impl From<Vec<u8>> for Secret {
+    fn from(bytes: Vec<u8>) -> Self {
+        Secret(bytes)
+    }
+}
+
+impl From<&[u8]> for Secret {
+    fn from(bytes: &[u8]) -> Self {
+        Secret(bytes.to_vec())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Secret {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Secret {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Secret {}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::{Sha256, Sha384, Sha512};
+
+    #[cfg(feature = "std")]
+    use crate::algorithm::secret::HmacKeyFromEnv;
+    use crate::algorithm::secret::Secret;
+    use crate::algorithm::{AlgorithmType, SigningAlgorithm};
+    use crate::error::Error;
+
+    #[test]
+    pub fn hmac_from_secret() -> Result<(), Error> {
+        let secret = Secret::from(&b"secret"[..]);
+        let from_secret: Hmac<Sha256> = secret.to_key()?;
+        let from_slice: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+
+        assert_eq!(
+            from_secret.sign("header", "claims")?,
+            from_slice.sign("header", "claims")?
+        );
+        assert!(!format!("{:?}", secret).contains("secret\""));
+        Ok(())
+    }
+
+    #[test]
+    pub fn strict_key() -> Result<(), Error> {
+        let secret = Secret::new([7; 48].to_vec());
+        let _: Hmac<Sha256> = secret.to_strict_key()?;
+        let _: Hmac<Sha384> = secret.to_strict_key()?;
+        match secret.to_strict_key::<Hmac<Sha512>>() {
+            Err(Error::WeakKey(algorithm)) => assert_eq!(algorithm, AlgorithmType::Hs512),
+            other => panic!("Expected a weak key: {:?}", other.is_ok()),
+        }
+
+        let pem = Secret::new(&include_bytes!("../../test/rs256-public.pem")[..]);
+        assert!(pem.len() >= 32);
+        assert!(matches!(
+            pem.to_strict_key::<Hmac<Sha256>>(),
+            Err(Error::WeakKey(AlgorithmType::Hs256))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn decode() -> Result<(), Error> {
+        assert_eq!(Secret::decode("secret")?.as_bytes(), b"secret");
+        assert_eq!(Secret::decode("base64:c2VjcmV0")?.as_bytes(), b"secret");
+        assert_eq!(Secret::decode("hex:73656372Ab")?.as_bytes(), b"secr\xab");
+        assert!(matches!(Secret::decode("hex:7"), Err(Error::Format)));
+        assert!(matches!(Secret::decode("hex:zz"), Err(Error::Format)));
+        assert!(matches!(Secret::decode("base64:!"), Err(Error::Base64(_))));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    pub fn hmac_key_from_env() -> Result<(), Error> {
+        std::env::set_var("JWT_SECRET_TEST_SHORT", "secret");
+        std::env::set_var("JWT_SECRET_TEST_LONG", format!("hex:{}", "00".repeat(48)));
+
+        let short = HmacKeyFromEnv::new("JWT_SECRET_TEST_SHORT");
+        assert!(matches!(
+            short.key::<Hmac<Sha256>>(),
+            Err(Error::WeakKey(AlgorithmType::Hs256))
+        ));
+
+        let long = HmacKeyFromEnv::new("JWT_SECRET_TEST_LONG");
+        let key: Hmac<Sha256> = long.key()?;
+        let from_slice: Hmac<Sha256> = Hmac::new_from_slice(&[0; 48])?;
+        assert_eq!(
+            key.sign("header", "claims")?,
+            from_slice.sign("header", "claims")?
+        );
+        let key = long.key_for(AlgorithmType::Hs384)?;
+        assert_eq!(SigningAlgorithm::algorithm_type(&key), AlgorithmType::Hs384);
+        assert!(long.key_for(AlgorithmType::Hs512).is_err());
+
+        let missing = HmacKeyFromEnv::new("JWT_SECRET_TEST_MISSING");
+        assert!(matches!(missing.secret(), Err(Error::Io(_))));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    pub fn zeroize() {
+        use zeroize::Zeroize;
+
+        let mut secret = Secret::new(b"secret".to_vec());
+        secret.zeroize();
+        assert!(secret.as_bytes().iter().all(|b| *b == 0));
+    }
+}
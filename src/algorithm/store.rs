@@ -1,12 +1,110 @@
-use std::borrow::Borrow;
-use std::collections::{BTreeMap, HashMap};
+use alloc::boxed::Box;
+use alloc::collections::btree_map::{self, BTreeMap};
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::borrow::Borrow;
+use core::iter::FromIterator;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
 use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::algorithm::key_id::{KeyIdSource, KeyIdStrategy};
+use crate::algorithm::{AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+use crate::header::JoseHeader;
 
 /// A store of keys that can be retrieved by key id.
 pub trait Store {
     type Algorithm: ?Sized;
 
     fn get(&self, key_id: &str) -> Option<&Self::Algorithm>;
+
+    /// Get the key for a token whose header declares `algorithm`. Stores that
+    /// know which algorithm each key was provisioned for can refuse to return
+    /// a key for any other algorithm. By default the algorithm is ignored.
+    fn get_for(&self, key_id: &str, algorithm: AlgorithmType) -> Option<&Self::Algorithm> {
+        let _ = algorithm;
+        self.get(key_id)
+    }
+}
+
+/// A store that chooses the key for a token from its whole header, so that
+/// custom header parameters, such as a tenant, can take part in selecting
+/// the key. Every `Store` chooses by the key id and algorithm of the header.
+/// ## Examples
+/// ```
+/// use hmac::{Hmac, Mac};
+/// use jwt::algorithm::store::{InMemoryStore, StoreWithHeader};
+/// use jwt::{AlgorithmType, Error, JoseHeader, SignWithKey, Store, Token};
+/// use serde::{Deserialize, Serialize};
+/// use sha2::Sha256;
+/// use std::collections::BTreeMap;
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct TenantHeader {
+///     alg: AlgorithmType,
+///     kid: String,
+///     tenant: String,
+/// }
+///
+/// impl JoseHeader for TenantHeader {
+///     fn algorithm_type(&self) -> AlgorithmType {
+///         self.alg
+///     }
+///
+///     fn key_id(&self) -> Option<&str> {
+///         Some(&self.kid)
+///     }
+/// }
+///
+/// struct Tenants(BTreeMap<String, InMemoryStore<Hmac<Sha256>>>);
+///
+/// impl StoreWithHeader<TenantHeader> for Tenants {
+///     type Algorithm = Hmac<Sha256>;
+///
+///     fn get_for_header(&self, header: &TenantHeader) -> Result<&Hmac<Sha256>, Error> {
+///         let keys = self.0.get(&header.tenant).ok_or(Error::NoKeyId)?;
+///         keys.get(&header.kid).ok_or_else(|| Error::NoKeyWithKeyId(header.kid.clone()))
+///     }
+/// }
+///
+/// let key: Hmac<Sha256> = Hmac::new_from_slice(b"acme-secret").unwrap();
+/// let mut acme = InMemoryStore::new();
+/// acme.insert("main", key.clone());
+/// let tenants = Tenants(BTreeMap::from([("acme".to_string(), acme)]));
+///
+/// let header = TenantHeader { kid: "main".into(), tenant: "acme".into(), ..Default::default() };
+/// let token = Token::new(header, BTreeMap::from([("sub", "someone")]))
+///     .sign_with_key(&key)
+///     .unwrap();
+///
+/// let token: Token<TenantHeader, BTreeMap<String, String>, _> =
+///     Token::parse_unverified(token.as_str()).unwrap();
+/// assert!(token.verify_with_header_store(&tenants).is_ok());
+/// ```
+pub trait StoreWithHeader<H: ?Sized> {
+    type Algorithm: ?Sized;
+
+    /// Get the key for a token with `header`, or fail with the reason no key
+    /// was found.
+    fn get_for_header(&self, header: &H) -> Result<&Self::Algorithm, Error>;
+}
+
+impl<S, H> StoreWithHeader<H> for S
+where
+    S: Store + ?Sized,
+    H: JoseHeader + ?Sized,
+{
+    type Algorithm = S::Algorithm;
+
+    fn get_for_header(&self, header: &H) -> Result<&S::Algorithm, Error> {
+        let key_id = header.key_id().ok_or(Error::NoKeyId)?;
+        self.get_for(key_id, header.algorithm_type())
+            .ok_or_else(|| Error::NoKeyWithKeyId(key_id.into()))
+    }
 }
 
 impl<K, A> Store for BTreeMap<K, A>
@@ -20,6 +118,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, A> Store for HashMap<K, A>
 where
     K: Borrow<str> + Ord + Hash,
@@ -30,3 +129,307 @@ where
         HashMap::get(self, key_id)
     }
 }
+
+impl<S: Store + ?Sized> Store for Arc<S> {
+    type Algorithm = S::Algorithm;
+
+    fn get(&self, key_id: &str) -> Option<&S::Algorithm> {
+        (**self).get(key_id)
+    }
+
+    fn get_for(&self, key_id: &str, algorithm: AlgorithmType) -> Option<&S::Algorithm> {
+        (**self).get_for(key_id, algorithm)
+    }
+}
+
+/// A store whose keys can be replaced while it is shared between threads,
+/// e.g. by a background task refreshing a key set. Readers take a snapshot,
+/// which is itself a `Store`, and keep using it unaffected by later
+/// replacements. Taking a snapshot only holds a lock long enough to clone an
+/// `Arc`.
+/// ## Examples
+/// ```
+/// use hmac::{Hmac, Mac};
+/// use jwt::algorithm::store::{InMemoryStore, SharedStore};
+/// use jwt::{SignWithStore, VerifyWithStore};
+/// use sha2::Sha256;
+/// use std::collections::BTreeMap;
+///
+/// let mut keys = InMemoryStore::new();
+/// keys.insert("first_key", Hmac::<Sha256>::new_from_slice(b"first").unwrap());
+/// let shared = SharedStore::new(keys);
+///
+/// let mut claims = BTreeMap::new();
+/// claims.insert("sub", "someone");
+/// let token_str = ("first_key", claims).sign_with_store(&shared.snapshot()).unwrap();
+///
+/// let refresher = shared.clone();
+/// std::thread::spawn(move || {
+///     let mut keys = InMemoryStore::new();
+///     keys.insert("second_key", Hmac::<Sha256>::new_from_slice(b"second").unwrap());
+///     refresher.replace(keys);
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let result: Result<BTreeMap<String, String>, _> =
+///     token_str.as_str().verify_with_store(&shared.snapshot());
+/// assert!(result.is_err());
+/// ```
+#[cfg(feature = "std")]
+pub struct SharedStore<S> {
+    current: Arc<RwLock<Arc<S>>>,
+}
+
+#[cfg(feature = "std")]
+impl<S> Clone for SharedStore<S> {
+    fn clone(&self) -> Self {
+        SharedStore {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> SharedStore<S> {
+    pub fn new(store: S) -> Self {
+        SharedStore {
+            current: Arc::new(RwLock::new(Arc::new(store))),
+        }
+    }
+
+    /// The current keys. Later replacements do not affect the snapshot.
+    pub fn snapshot(&self) -> Arc<S> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(&current)
+    }
+
+    /// Atomically replace the keys, returning the previous ones.
+    pub fn replace(&self, store: S) -> Arc<S> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        core::mem::replace(&mut *current, Arc::new(store))
+    }
+}
+
+/// A store of keys held in memory, with either one key type or boxed trait
+/// objects, such as `Box<dyn VerifyingAlgorithm + Send + Sync>`, to mix
+/// algorithms.
+/// ## Examples
+/// ```
+/// use hmac::{Hmac, Mac};
+/// use jwt::algorithm::store::InMemoryStore;
+/// use jwt::{Store, VerifyingAlgorithm};
+/// use sha2::{Sha256, Sha512};
+///
+/// let mut store: InMemoryStore<Box<dyn VerifyingAlgorithm + Send + Sync>> =
+///     InMemoryStore::new();
+/// store.insert_boxed("first_key", Hmac::<Sha256>::new_from_slice(b"first").unwrap());
+/// store.insert_boxed("second_key", Hmac::<Sha512>::new_from_slice(b"second").unwrap());
+///
+/// assert!(store.get("first_key").is_some());
+/// assert!(store.get("third_key").is_none());
+/// assert_eq!(store.key_ids().collect::<Vec<_>>(), ["first_key", "second_key"]);
+/// ```
+pub struct InMemoryStore<A> {
+    keys: BTreeMap<String, A>,
+}
+
+impl<A> Default for InMemoryStore<A> {
+    fn default() -> Self {
+        InMemoryStore {
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A> InMemoryStore<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key, returning the previous key with the same id.
+    pub fn insert(&mut self, key_id: impl Into<String>, key: A) -> Option<A> {
+        self.keys.insert(key_id.into(), key)
+    }
+
+    pub fn remove(&mut self, key_id: &str) -> Option<A> {
+        self.keys.remove(key_id)
+    }
+
+    pub fn contains(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn key_ids(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &A)> {
+        self.keys.iter().map(|(key_id, key)| (key_id.as_str(), key))
+    }
+}
+
+impl<A: KeyIdSource> InMemoryStore<A> {
+    /// Add a key under the id `strategy` derives for it, returning the id.
+    pub fn insert_with_key_id(
+        &mut self,
+        strategy: &KeyIdStrategy,
+        key: A,
+    ) -> Result<String, Error> {
+        let key_id = strategy.key_id(&key)?;
+        self.keys.insert(key_id.clone(), key);
+        Ok(key_id)
+    }
+}
+
+impl InMemoryStore<Box<dyn VerifyingAlgorithm>> {
+    /// Box and add a verifying key, returning the previous key with the same
+    /// id.
+    pub fn insert_boxed(
+        &mut self,
+        key_id: impl Into<String>,
+        key: impl VerifyingAlgorithm + 'static,
+    ) -> Option<Box<dyn VerifyingAlgorithm>> {
+        self.insert(key_id, Box::new(key))
+    }
+}
+
+impl InMemoryStore<Box<dyn VerifyingAlgorithm + Send + Sync>> {
+    /// Box and add a verifying key, returning the previous key with the same
+    /// id.
+    pub fn insert_boxed(
+        &mut self,
+        key_id: impl Into<String>,
+        key: impl VerifyingAlgorithm + Send + Sync + 'static,
+    ) -> Option<Box<dyn VerifyingAlgorithm + Send + Sync>> {
+        self.insert(key_id, Box::new(key))
+    }
+}
+
+impl InMemoryStore<Box<dyn SigningAlgorithm>> {
+    /// Box and add a signing key, returning the previous key with the same
+    /// id.
+    pub fn insert_boxed(
+        &mut self,
+        key_id: impl Into<String>,
+        key: impl SigningAlgorithm + 'static,
+    ) -> Option<Box<dyn SigningAlgorithm>> {
+        self.insert(key_id, Box::new(key))
+    }
+}
+
+impl InMemoryStore<Box<dyn SigningAlgorithm + Send + Sync>> {
+    /// Box and add a signing key, returning the previous key with the same
+    /// id.
+    pub fn insert_boxed(
+        &mut self,
+        key_id: impl Into<String>,
+        key: impl SigningAlgorithm + Send + Sync + 'static,
+    ) -> Option<Box<dyn SigningAlgorithm + Send + Sync>> {
+        self.insert(key_id, Box::new(key))
+    }
+}
+
+impl<A> Store for InMemoryStore<A> {
+    type Algorithm = A;
+
+    fn get(&self, key_id: &str) -> Option<&A> {
+        self.keys.get(key_id)
+    }
+}
+
+impl<K: Into<String>, A> FromIterator<(K, A)> for InMemoryStore<A> {
+    fn from_iter<I: IntoIterator<Item = (K, A)>>(iter: I) -> Self {
+        InMemoryStore {
+            keys: iter.into_iter().map(|(k, a)| (k.into(), a)).collect(),
+        }
+    }
+}
+
+impl<A> Extend<(String, A)> for InMemoryStore<A> {
+    fn extend<I: IntoIterator<Item = (String, A)>>(&mut self, iter: I) {
+        self.keys.extend(iter)
+    }
+}
+
+impl<A> IntoIterator for InMemoryStore<A> {
+    type Item = (String, A);
+    type IntoIter = btree_map::IntoIter<String, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.into_iter()
+    }
+}
+
+/// A store that pins every key to the algorithm it was provisioned for.
+/// Tokens signed or verified through it must declare that algorithm in their
+/// header, regardless of which algorithms the key itself could be used with.
+/// ## Examples
+/// ```
+/// use hmac::{Hmac, Mac};
+/// use jwt::algorithm::store::PinnedStore;
+/// use jwt::{AlgorithmType, Store};
+/// use sha2::Sha256;
+///
+/// let mut store = PinnedStore::new();
+/// let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret").unwrap();
+/// store.insert("key", AlgorithmType::Hs256, key);
+///
+/// assert!(store.get_for("key", AlgorithmType::Hs256).is_some());
+/// assert!(store.get_for("key", AlgorithmType::Rs256).is_none());
+/// ```
+pub struct PinnedStore<A> {
+    keys: BTreeMap<String, (AlgorithmType, A)>,
+}
+
+impl<A> Default for PinnedStore<A> {
+    fn default() -> Self {
+        PinnedStore {
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A> PinnedStore<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key that may only be used with `algorithm`, returning the
+    /// previous key with the same id.
+    pub fn insert(
+        &mut self,
+        key_id: impl Into<String>,
+        algorithm: AlgorithmType,
+        key: A,
+    ) -> Option<(AlgorithmType, A)> {
+        self.keys.insert(key_id.into(), (algorithm, key))
+    }
+
+    pub fn remove(&mut self, key_id: &str) -> Option<(AlgorithmType, A)> {
+        self.keys.remove(key_id)
+    }
+}
+
+impl<A> Store for PinnedStore<A> {
+    type Algorithm = A;
+
+    fn get(&self, key_id: &str) -> Option<&A> {
+        self.keys.get(key_id).map(|(_, key)| key)
+    }
+
+    fn get_for(&self, key_id: &str, algorithm: AlgorithmType) -> Option<&A> {
+        match self.keys.get(key_id) {
+            Some((pinned, key)) if *pinned == algorithm => Some(key),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! A record of every signature verification, for streaming authentication
+//! decisions to a log or SIEM without wrapping each call site. Once an
+//! `AuditSink` is installed with [set_sink](fn.set_sink.html), it receives an
+//! `AuditRecord` after each token is checked against a key, whether the
+//! signature was accepted or not, and after each token served from a
+//! [verification cache](../cache/index.html). Tokens are identified by their
+//! fingerprint, never by the token itself.
+//!
+//! Like a `metrics` recorder, the sink is global to the process and belongs
+//! to the application: it sees the verifications of every library in the
+//! process, so libraries should leave it to the application to install. It
+//! can be replaced or removed at any time, e.g. between tests.
+//! ## Examples
+//! ```
+//! use jwt::audit::{self, AuditRecord};
+//!
+//! audit::set_sink(|record: &AuditRecord| {
+//!     eprintln!(
+//!         "jwt verification kid={:?} alg={:?} sub={:?} failure={:?}",
+//!         record.key_id, record.algorithm, record.subject, record.failure
+//!     )
+//! });
+//! ```
+
+use std::string::String;
+use std::sync::{Arc, RwLock};
+
+use serde::Deserialize;
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::RegisteredClaims;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::{fingerprint, Unverified};
+use crate::FromBase64;
+
+/// The outcome of verifying one token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditRecord {
+    pub key_id: Option<String>,
+    pub algorithm: AlgorithmType,
+    /// The `iss` claim, unverified if the signature was rejected.
+    pub issuer: Option<String>,
+    /// The `sub` claim, unverified if the signature was rejected.
+    pub subject: Option<String>,
+    pub verified: bool,
+    /// The kind of error the token was rejected with, such as
+    /// `invalid_signature` or `algorithm_mismatch`.
+    pub failure: Option<&'static str>,
+    /// Whether the token was served from a verification cache, where it was
+    /// recorded as verified before, without its signature being checked.
+    pub cached: bool,
+    pub fingerprint: String,
+}
+
+/// Receives the record of every verification. Closures taking a record can
+/// be used as sinks.
+pub trait AuditSink: Send + Sync {
+    fn record(&self, record: &AuditRecord);
+}
+
+impl<F: Fn(&AuditRecord) + Send + Sync> AuditSink for F {
+    fn record(&self, record: &AuditRecord) {
+        self(record)
+    }
+}
+
+static SINK: RwLock<Option<Arc<dyn AuditSink>>> = RwLock::new(None);
+
+/// Install the sink of the application, returning the sink it replaces.
+pub fn set_sink(sink: impl AuditSink + 'static) -> Option<Arc<dyn AuditSink>> {
+    replace(Some(Arc::new(sink)))
+}
+
+/// Remove the installed sink, returning it. Verifications are no longer
+/// recorded until another sink is installed.
+pub fn take_sink() -> Option<Arc<dyn AuditSink>> {
+    replace(None)
+}
+
+fn replace(sink: Option<Arc<dyn AuditSink>>) -> Option<Arc<dyn AuditSink>> {
+    let mut installed = SINK.write().unwrap_or_else(|e| e.into_inner());
+    core::mem::replace(&mut *installed, sink)
+}
+
+/// The installed sink, if any, cloned so that it is called without holding
+/// the lock, and may itself replace the sink.
+fn sink() -> Option<Arc<dyn AuditSink>> {
+    SINK.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The claims read for the record, parsed only when a sink is installed.
+#[derive(Default, Deserialize)]
+struct Parties {
+    iss: Option<String>,
+    sub: Option<String>,
+}
+
+pub(crate) fn verified(
+    header: &impl JoseHeader,
+    unverified: &Unverified<'_>,
+    result: &Result<(), Error>,
+) {
+    let sink = match sink() {
+        Some(sink) => sink,
+        None => return,
+    };
+
+    let parties = Parties::from_base64(unverified.claims_str).unwrap_or_default();
+    sink.record(&AuditRecord {
+        key_id: header.key_id().map(String::from),
+        algorithm: header.algorithm_type(),
+        issuer: parties.iss,
+        subject: parties.sub,
+        verified: result.is_ok(),
+        failure: result.as_ref().err().map(crate::instrument::error_kind),
+        cached: false,
+        fingerprint: fingerprint(&[
+            unverified.header_str,
+            unverified.claims_str,
+            unverified.signature_str,
+        ]),
+    });
+}
+
+pub(crate) fn cache_hit(header: &impl JoseHeader, claims: &RegisteredClaims, token_str: &str) {
+    let sink = match sink() {
+        Some(sink) => sink,
+        None => return,
+    };
+
+    sink.record(&AuditRecord {
+        key_id: header.key_id().map(String::from),
+        algorithm: header.algorithm_type(),
+        issuer: claims.issuer.clone(),
+        subject: claims.subject.clone(),
+        verified: true,
+        failure: None,
+        cached: true,
+        fingerprint: fingerprint(&[token_str]),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::algorithm::AlgorithmType;
+    use crate::audit::{self, AuditRecord};
+    use crate::cache::{verify_with_cache_at, LruCache};
+    use crate::claims::RegisteredClaims;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::Token;
+
+    static RECORDS: Mutex<Vec<AuditRecord>> = Mutex::new(Vec::new());
+
+    #[test]
+    pub fn records() -> Result<(), Error> {
+        audit::set_sink(|record: &AuditRecord| RECORDS.lock().unwrap().push(record.clone()));
+
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"audit")?;
+        let header = Header {
+            key_id: Some("audit-key".into()),
+            ..Default::default()
+        };
+        let claims = RegisteredClaims {
+            issuer: Some("mikkyang.com".into()),
+            subject: Some("audited".into()),
+            ..Default::default()
+        };
+        let signed = Token::new(header, claims).sign_with_key(&key)?;
+        let fingerprint = signed.fingerprint();
+
+        let _: Token<Header, RegisteredClaims, _> = signed.as_str().verify_with_key(&key)?;
+        let other_key: Hmac<Sha256> = Hmac::new_from_slice(b"other")?;
+        let rejected: Result<Token<Header, RegisteredClaims, _>, _> =
+            signed.as_str().verify_with_key(&other_key);
+        assert!(rejected.is_err());
+
+        let records: Vec<AuditRecord> = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.fingerprint == fingerprint)
+            .cloned()
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].key_id.as_deref(), Some("audit-key"));
+        assert_eq!(records[0].algorithm, AlgorithmType::Hs256);
+        assert_eq!(records[0].issuer.as_deref(), Some("mikkyang.com"));
+        assert_eq!(records[0].subject.as_deref(), Some("audited"));
+        assert!(records[0].verified);
+        assert_eq!(records[0].failure, None);
+        assert!(!records[0].cached);
+        assert!(!records[1].verified);
+        assert_eq!(records[1].failure, Some("invalid_signature"));
+
+        // Tokens served from a cache are recorded as well.
+        let claims = RegisteredClaims {
+            subject: Some("cached".into()),
+            expiration: Some(2000),
+            ..Default::default()
+        };
+        let signed = Token::new(Header::default(), claims).sign_with_key(&key)?;
+        let cache = LruCache::new(10);
+        for _ in 0..2 {
+            verify_with_cache_at::<Header, RegisteredClaims>(
+                signed.as_str(),
+                "audit-key",
+                &key,
+                &cache,
+                1000,
+            )?;
+        }
+        let records: Vec<AuditRecord> = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.fingerprint == signed.fingerprint())
+            .cloned()
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert!(!records[0].cached);
+        assert!(records[1].cached);
+        assert!(records[1].verified);
+        assert_eq!(records[1].subject.as_deref(), Some("cached"));
+
+        // Once the sink is removed, nothing more is recorded.
+        assert!(audit::take_sink().is_some());
+        let _: Token<Header, RegisteredClaims, _> = signed.as_str().verify_with_key(&key)?;
+        let recorded = RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.fingerprint == signed.fingerprint())
+            .count();
+        assert_eq!(recorded, 2);
+        assert!(audit::set_sink(|_: &AuditRecord| ()).is_none());
+        Ok(())
+    }
+}
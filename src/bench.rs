@@ -0,0 +1,95 @@
+//! Fixtures shared by the benchmarks in `benches/`, enabled by the `bench`
+//! feature. They stay the same between releases, so that results can be
+//! compared across upgrades of the crate or its cryptography backends. Run
+//! the suite with `cargo bench --features bench`, adding `openssl` for the
+//! RSA and ECDSA benchmarks.
+
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+#[cfg(feature = "openssl")]
+use crate::algorithm::openssl::PKeyWithDigest;
+use crate::header::Header;
+use crate::token::signed::SignWithKey;
+use crate::token::Unsigned;
+use crate::Token;
+
+/// The secret used for every HMAC key.
+pub const SECRET: &[u8] = b"benchmark-secret";
+
+/// Claims with `count` private claims in addition to `iss` and `sub`.
+pub fn claims(count: usize) -> BTreeMap<String, String> {
+    let mut claims: BTreeMap<_, _> = (0..count)
+        .map(|i| (format!("claim_{}", i), format!("value_{}", i)))
+        .collect();
+    claims.insert("iss".into(), "mikkyang.com".into());
+    claims.insert("sub".into(), "someone".into());
+    claims
+}
+
+/// An unsigned token with a default header and `count` private claims.
+pub fn token(count: usize) -> Token<Header, BTreeMap<String, String>, Unsigned> {
+    Token::new(Header::default(), claims(count))
+}
+
+pub fn hs256_key() -> Hmac<Sha256> {
+    Hmac::new_from_slice(SECRET).expect("HMAC accepts keys of any length")
+}
+
+/// A token string signed with [hs256_key](fn.hs256_key.html).
+pub fn hs256_token(count: usize) -> String {
+    claims(count)
+        .sign_with_key(&hs256_key())
+        .expect("benchmark claims can be signed")
+}
+
+/// A store of `count` HS256 keys with ids `key_0`, `key_1`, etc.
+pub fn key_store(count: usize) -> BTreeMap<String, Hmac<Sha256>> {
+    (0..count)
+        .map(|i| (format!("key_{}", i), hs256_key()))
+        .collect()
+}
+
+#[cfg(feature = "openssl")]
+mod openssl_keys {
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private, Public};
+
+    use crate::algorithm::openssl::PKeyWithDigest;
+
+    pub fn private(pem: &[u8]) -> PKeyWithDigest<Private> {
+        PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(pem).expect("valid private key fixture"),
+        }
+    }
+
+    pub fn public(pem: &[u8]) -> PKeyWithDigest<Public> {
+        PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::public_key_from_pem(pem).expect("valid public key fixture"),
+        }
+    }
+}
+
+#[cfg(feature = "openssl")]
+pub fn rs256_private_key() -> PKeyWithDigest<openssl::pkey::Private> {
+    openssl_keys::private(include_bytes!("../test/rs256-private.pem"))
+}
+
+#[cfg(feature = "openssl")]
+pub fn rs256_public_key() -> PKeyWithDigest<openssl::pkey::Public> {
+    openssl_keys::public(include_bytes!("../test/rs256-public.pem"))
+}
+
+#[cfg(feature = "openssl")]
+pub fn es256_private_key() -> PKeyWithDigest<openssl::pkey::Private> {
+    openssl_keys::private(include_bytes!("../test/es256-private.pem"))
+}
+
+#[cfg(feature = "openssl")]
+pub fn es256_public_key() -> PKeyWithDigest<openssl::pkey::Public> {
+    openssl_keys::public(include_bytes!("../test/es256-public.pem"))
+}
@@ -0,0 +1,627 @@
+//! Caching of verification results, for clients that send the same token
+//! with every request. A token is cached under its fingerprint and the key
+//! that verified it until its `exp`, so a repeat of it skips signature
+//! verification. Tokens without an `exp` are never cached.
+//!
+//! A cached token stays valid even if the key that verified it is rotated
+//! out, so caches should be cleared whenever keys are revoked.
+//!
+//! Failures can be remembered too: a `FailureHook` is told about every token
+//! that fails to verify, and can refuse further attempts with the same token
+//! or from the same client, throttling brute-force attempts.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::cache::{verify_with_cache_at, LruCache};
+//! use jwt::{Claims, Header, RegisteredClaims, SignWithKey};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let token_str = Claims::new(RegisteredClaims {
+//!     expiration: Some(1302319100),
+//!     ..Default::default()
+//! })
+//! .sign_with_key(&key)
+//! .unwrap();
+//!
+//! let cache = LruCache::new(1000);
+//! for _ in 0..3 {
+//!     verify_with_cache_at::<Header, Claims>(&token_str, "key", &key, &cache, 1302319000)
+//!         .unwrap();
+//! }
+//! assert_eq!(cache.len(), 1);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use crate::algorithm::VerifyingAlgorithm;
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::encoding;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::verified::VerifyWithKey;
+use crate::token::{fingerprint, Unverified, Verified};
+use crate::{FromBase64, Token};
+
+/// A cache of verified tokens, keyed by the token's fingerprint.
+/// Implementations are shared between requests, so take `&self`.
+pub trait VerificationCache<V> {
+    /// The cached value, if it has not expired by `now`.
+    fn get(&self, fingerprint: &str, now: SecondsSinceEpoch) -> Option<V>;
+
+    /// Cache `value` until `expires_at`.
+    fn insert(&self, fingerprint: String, value: V, expires_at: SecondsSinceEpoch);
+
+    /// Remove a single token, returning whether it was cached.
+    fn invalidate(&self, fingerprint: &str) -> bool;
+
+    /// Remove every token, e.g. after a key has been revoked.
+    fn clear(&self);
+}
+
+/// A verification cache holding at most a fixed number of tokens, evicting
+/// the least recently used when full.
+pub struct LruCache<V> {
+    capacity: usize,
+    inner: Mutex<Lru<V>>,
+}
+
+struct Lru<V> {
+    entries: HashMap<String, Entry<V>>,
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    expires_at: SecondsSinceEpoch,
+    last_used: u64,
+}
+
+impl<V> Lru<V> {
+    fn touch(&mut self, fingerprint: &str) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(fingerprint) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, fingerprint.to_owned());
+        }
+    }
+
+    fn remove(&mut self, fingerprint: &str) -> Option<Entry<V>> {
+        let entry = self.entries.remove(fingerprint)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry)
+    }
+}
+
+impl<V> LruCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            inner: Mutex::new(Lru {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<V>> {
+        // A panic while holding the lock cannot leave the maps inconsistent
+        // in a way that matters for a cache, so poisoning is ignored.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<V: Clone> VerificationCache<V> for LruCache<V> {
+    fn get(&self, fingerprint: &str, now: SecondsSinceEpoch) -> Option<V> {
+        let mut lru = self.lock();
+        let expired = now >= lru.entries.get(fingerprint)?.expires_at;
+        if expired {
+            lru.remove(fingerprint);
+            return None;
+        }
+
+        lru.touch(fingerprint);
+        lru.entries
+            .get(fingerprint)
+            .map(|entry| entry.value.clone())
+    }
+
+    fn insert(&self, fingerprint: String, value: V, expires_at: SecondsSinceEpoch) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lru = self.lock();
+        lru.remove(&fingerprint);
+        while lru.entries.len() >= self.capacity {
+            let oldest = match lru.recency.values().next() {
+                Some(oldest) => oldest.clone(),
+                None => break,
+            };
+            lru.remove(&oldest);
+        }
+
+        let entry = Entry {
+            value,
+            expires_at,
+            last_used: 0,
+        };
+        lru.entries.insert(fingerprint.clone(), entry);
+        lru.touch(&fingerprint);
+    }
+
+    fn invalidate(&self, fingerprint: &str) -> bool {
+        self.lock().remove(fingerprint).is_some()
+    }
+
+    fn clear(&self) {
+        let mut lru = self.lock();
+        lru.entries.clear();
+        lru.recency.clear();
+    }
+}
+
+/// Receives failed verifications, so that repeated attempts with the same
+/// bad token or from the same client can be refused without verifying them.
+pub trait FailureHook {
+    /// Record that the token with `fingerprint`, sent by `source`, failed to
+    /// verify with `error`.
+    fn record_failure(
+        &self,
+        fingerprint: &str,
+        source: Option<&str>,
+        error: &Error,
+        now: SecondsSinceEpoch,
+    );
+
+    /// Whether the token or its source has failed too often to be tried.
+    fn is_throttled(&self, fingerprint: &str, source: Option<&str>, now: SecondsSinceEpoch)
+        -> bool;
+}
+
+/// Totals of a `FailureTracker`, for exporting as metrics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FailureCounters {
+    /// Failed verifications recorded.
+    pub failures: u64,
+    /// Verifications refused as throttled.
+    pub throttled: u64,
+}
+
+/// Counts failures per token and per source within a window of time,
+/// throttling either once it reaches a limit. A token that failed once will
+/// fail again, so tokens are throttled after a single failure; sources are
+/// throttled after `max_failures`. At most `capacity` tokens and sources are
+/// tracked each, after which new ones are ignored until old windows end.
+pub struct FailureTracker {
+    max_failures: u32,
+    window: SecondsSinceEpoch,
+    capacity: usize,
+    inner: Mutex<Failures>,
+}
+
+#[derive(Default)]
+struct Failures {
+    tokens: HashMap<String, Window>,
+    sources: HashMap<String, Window>,
+    counters: FailureCounters,
+}
+
+#[derive(Clone, Copy)]
+struct Window {
+    count: u32,
+    started_at: SecondsSinceEpoch,
+}
+
+fn record(
+    windows: &mut HashMap<String, Window>,
+    key: &str,
+    capacity: usize,
+    length: SecondsSinceEpoch,
+    now: SecondsSinceEpoch,
+) {
+    if windows.len() >= capacity && !windows.contains_key(key) {
+        windows.retain(|_, window| now < window.started_at.saturating_add(length));
+        if windows.len() >= capacity {
+            return;
+        }
+    }
+
+    let window = windows.entry(key.to_owned()).or_insert(Window {
+        count: 0,
+        started_at: now,
+    });
+    if now >= window.started_at.saturating_add(length) {
+        *window = Window {
+            count: 0,
+            started_at: now,
+        };
+    }
+    window.count = window.count.saturating_add(1);
+}
+
+fn count(
+    windows: &HashMap<String, Window>,
+    key: &str,
+    length: SecondsSinceEpoch,
+    now: SecondsSinceEpoch,
+) -> u32 {
+    match windows.get(key) {
+        Some(window) if now < window.started_at.saturating_add(length) => window.count,
+        _ => 0,
+    }
+}
+
+impl FailureTracker {
+    /// Throttle sources after `max_failures` failures within `window`
+    /// seconds.
+    pub fn new(max_failures: u32, window: SecondsSinceEpoch, capacity: usize) -> Self {
+        FailureTracker {
+            max_failures,
+            window,
+            capacity,
+            inner: Mutex::new(Failures::default()),
+        }
+    }
+
+    /// Failures of `source` within the current window.
+    pub fn source_failures(&self, source: &str, now: SecondsSinceEpoch) -> u32 {
+        count(&self.lock().sources, source, self.window, now)
+    }
+
+    /// Forget the failures of `source`, e.g. after it authenticated in
+    /// another way.
+    pub fn reset_source(&self, source: &str) -> bool {
+        self.lock().sources.remove(source).is_some()
+    }
+
+    pub fn counters(&self) -> FailureCounters {
+        self.lock().counters
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Failures> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FailureHook for FailureTracker {
+    fn record_failure(
+        &self,
+        fingerprint: &str,
+        source: Option<&str>,
+        _: &Error,
+        now: SecondsSinceEpoch,
+    ) {
+        let mut failures = self.lock();
+        failures.counters.failures += 1;
+        record(
+            &mut failures.tokens,
+            fingerprint,
+            self.capacity,
+            self.window,
+            now,
+        );
+        if let Some(source) = source {
+            record(
+                &mut failures.sources,
+                source,
+                self.capacity,
+                self.window,
+                now,
+            );
+        }
+    }
+
+    fn is_throttled(
+        &self,
+        fingerprint: &str,
+        source: Option<&str>,
+        now: SecondsSinceEpoch,
+    ) -> bool {
+        let mut failures = self.lock();
+        let throttled = count(&failures.tokens, fingerprint, self.window, now) > 0
+            || source.is_some_and(|source| {
+                count(&failures.sources, source, self.window, now) >= self.max_failures
+            });
+        if throttled {
+            failures.counters.throttled += 1;
+        }
+        throttled
+    }
+}
+
+/// Verify a token, or return it from the cache if the same token was
+/// verified before with the same key and has not expired. Only the signature
+/// is checked, as with `verify_with_key`; claims still have to be validated.
+///
+/// `key_id` names the key, and is part of the cache key along with the
+/// algorithm of the key, so that a cache can be shared between keys or
+/// tenants: a token verified with one key is never served from the cache for
+/// another. Every key sharing a cache needs a distinct id.
+pub fn verify_with_cache<H, C>(
+    token_str: &str,
+    key_id: &str,
+    key: &impl VerifyingAlgorithm,
+    cache: &impl VerificationCache<(H, C)>,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader + Clone,
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone,
+{
+    verify_with_cache_at(token_str, key_id, key, cache, crate::clock::now())
+}
+
+/// Like [verify_with_cache](fn.verify_with_cache.html), at the time `now`.
+pub fn verify_with_cache_at<H, C>(
+    token_str: &str,
+    key_id: &str,
+    key: &impl VerifyingAlgorithm,
+    cache: &impl VerificationCache<(H, C)>,
+    now: SecondsSinceEpoch,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader + Clone,
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone,
+{
+    verify_fingerprinted(token_str, key_id, key, cache, now)
+}
+
+/// Verify a token like [verify_with_cache](fn.verify_with_cache.html),
+/// reporting failures to `failures` and refusing tokens it throttles with
+/// `Error::Throttled`. `source` identifies the client, e.g. its address, so
+/// that failures can be limited per client as well as per token.
+pub fn verify_throttled<H, C>(
+    token_str: &str,
+    source: Option<&str>,
+    key_id: &str,
+    key: &impl VerifyingAlgorithm,
+    cache: &impl VerificationCache<(H, C)>,
+    failures: &impl FailureHook,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader + Clone,
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone,
+{
+    verify_throttled_at(
+        token_str,
+        source,
+        key_id,
+        key,
+        cache,
+        failures,
+        crate::clock::now(),
+    )
+}
+
+/// Like [verify_throttled](fn.verify_throttled.html), at the time `now`.
+pub fn verify_throttled_at<H, C>(
+    token_str: &str,
+    source: Option<&str>,
+    key_id: &str,
+    key: &impl VerifyingAlgorithm,
+    cache: &impl VerificationCache<(H, C)>,
+    failures: &impl FailureHook,
+    now: SecondsSinceEpoch,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader + Clone,
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone,
+{
+    let fingerprint = fingerprint(&[token_str]);
+    if failures.is_throttled(&fingerprint, source, now) {
+        return Err(Error::Throttled);
+    }
+
+    verify_fingerprinted(token_str, key_id, key, cache, now).inspect_err(|error| {
+        failures.record_failure(&fingerprint, source, error, now);
+    })
+}
+
+fn verify_fingerprinted<H, C>(
+    token_str: &str,
+    key_id: &str,
+    key: &impl VerifyingAlgorithm,
+    cache: &impl VerificationCache<(H, C)>,
+    now: SecondsSinceEpoch,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader + Clone,
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone,
+{
+    let algorithm = format!("{:?}", key.algorithm_type());
+    let fingerprint = cache_key(&[&algorithm, key_id, token_str]);
+    if let Some((header, claims)) = cache.get(&fingerprint, now) {
+        crate::audit::cache_hit(&header, claims.as_ref(), token_str);
+        return Ok(Token {
+            header,
+            claims,
+            signature: Verified,
+        });
+    }
+
+    let unverified: Token<H, C, Unverified> = Token::parse_unverified(token_str)?;
+    let token: Token<H, C, Verified> = unverified.verify_with_key(key)?;
+    if let Some(expiration) = token.claims.as_ref().expiration {
+        if expiration > now {
+            let value = (token.header.clone(), token.claims.clone());
+            cache.insert(fingerprint, value, expiration);
+        }
+    }
+    Ok(token)
+}
+
+/// A hash of each part with its length, so that no part can run into the
+/// next one, e.g. a key id containing a `.` into the token.
+fn cache_key(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part.as_bytes());
+    }
+    encoding::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::cache::{
+        verify_throttled_at, verify_with_cache_at, FailureCounters, FailureTracker, LruCache,
+        VerificationCache,
+    };
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+
+    fn sign(key: &Hmac<Sha256>, subject: &str, expiration: Option<u64>) -> String {
+        let claims = Claims::new(RegisteredClaims {
+            subject: Some(subject.into()),
+            expiration,
+            ..Default::default()
+        });
+        claims.sign_with_key(key).unwrap()
+    }
+
+    #[test]
+    pub fn lru() {
+        let cache = LruCache::new(2);
+        cache.insert("a".into(), 1, 100);
+        cache.insert("b".into(), 2, 100);
+        assert_eq!(cache.get("a", 0), Some(1));
+
+        cache.insert("c".into(), 3, 100);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b", 0), None);
+        assert_eq!(cache.get("a", 0), Some(1));
+
+        assert_eq!(cache.get("c", 100), None);
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.invalidate("a"));
+        assert!(!cache.invalidate("a"));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    pub fn verify() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let cache = LruCache::new(10);
+        let verify = |token_str: &str, key_id, key: &Hmac<Sha256>, now| {
+            verify_with_cache_at::<Header, Claims>(token_str, key_id, key, &cache, now)
+        };
+
+        let token_str = sign(&key, "someone", Some(2000));
+        let token = verify(&token_str, "key", &key, 1000)?;
+        assert_eq!(
+            token.claims().registered.subject.as_deref(),
+            Some("someone")
+        );
+        assert_eq!(cache.len(), 1);
+        verify(&token_str, "key", &key, 1500)?;
+        assert_eq!(cache.len(), 1);
+        assert!(verify(&token_str, "key", &key, 2000).is_ok());
+        assert!(cache.is_empty());
+
+        let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+        let forged = sign(&wrong_key, "someone", Some(2000));
+        assert!(verify(&forged, "key", &key, 1000).is_err());
+
+        let no_expiration = sign(&key, "someone", None);
+        verify(&no_expiration, "key", &key, 1000)?;
+        assert!(cache.is_empty());
+
+        verify(&token_str, "key", &key, 1000)?;
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn shared_between_keys() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+        let cache = LruCache::new(10);
+
+        let token_str = sign(&key, "someone", Some(2000));
+        verify_with_cache_at::<Header, Claims>(&token_str, "tenant-a", &key, &cache, 1000)?;
+        assert_eq!(cache.len(), 1);
+
+        // The token of one tenant misses the cache for another, and is
+        // verified with its key.
+        match verify_with_cache_at::<Header, Claims>(
+            &token_str, "tenant-b", &wrong_key, &cache, 1000,
+        ) {
+            Err(Error::RustCryptoMac(_)) => (),
+            other => panic!("Expected a cache miss: {:?}", other.err()),
+        }
+        assert_eq!(cache.len(), 1);
+
+        // Nor can a key id containing a `.` take the place of part of the
+        // token.
+        let (header_str, rest) = token_str.split_once('.').unwrap();
+        let key_id = format!("tenant-a.{}", header_str);
+        match verify_with_cache_at::<Header, Claims>(rest, &key_id, &wrong_key, &cache, 1000) {
+            Err(Error::NoSignatureComponent) => (),
+            other => panic!("Expected a cache miss: {:?}", other.err()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn throttle() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+        let cache = LruCache::<(Header, Claims)>::new(10);
+        let failures = FailureTracker::new(2, 60, 10);
+
+        let forged = sign(&wrong_key, "someone", Some(2000));
+        let verify = |token_str: &str, source, now| {
+            verify_throttled_at::<Header, Claims>(
+                token_str, source, "key", &key, &cache, &failures, now,
+            )
+        };
+        assert!(matches!(
+            verify(&forged, Some("10.0.0.1"), 1000),
+            Err(Error::RustCryptoMac(_))
+        ));
+        assert!(matches!(verify(&forged, None, 1000), Err(Error::Throttled)));
+        assert_eq!(failures.source_failures("10.0.0.1", 1000), 1);
+
+        let other_forged = sign(&wrong_key, "someone else", Some(2000));
+        assert!(verify(&other_forged, Some("10.0.0.1"), 1010).is_err());
+        let token_str = sign(&key, "someone", Some(2000));
+        match verify(&token_str, Some("10.0.0.1"), 1020) {
+            Err(Error::Throttled) => (),
+            other => panic!("Expected the source to be throttled: {:?}", other),
+        }
+        verify(&token_str, Some("10.0.0.2"), 1020)?;
+
+        // Windows end, and sources can be reset early.
+        verify(&token_str, Some("10.0.0.1"), 1070)?;
+        assert!(verify(&forged, None, 1070).is_err());
+        assert!(failures.reset_source("10.0.0.1"));
+
+        let expected = FailureCounters {
+            failures: 3,
+            throttled: 2,
+        };
+        assert_eq!(failures.counters(), expected);
+        Ok(())
+    }
+}
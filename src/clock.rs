@@ -0,0 +1,67 @@
+//! Sources of the current time for time based claims. With the `std` feature
+//! the system time is used. On `wasm32-unknown-unknown`, where the system time
+//! is unavailable, the `std` feature requires the `js` feature, which reads
+//! the time from `js_sys::Date` instead.
+//! ## Examples
+//! ```
+//! use jwt::clock::{Clock, SystemClock};
+//!
+//! let now = SystemClock.now();
+//! let fixed = || 1302319100;
+//! assert_eq!(fixed.now(), 1302319100);
+//! # assert!(now > 1302319100);
+//! ```
+
+use crate::claims::SecondsSinceEpoch;
+
+/// A source of the current time, in seconds since the epoch.
+pub trait Clock {
+    fn now(&self) -> SecondsSinceEpoch;
+}
+
+impl<F: Fn() -> SecondsSinceEpoch> Clock for F {
+    fn now(&self) -> SecondsSinceEpoch {
+        self()
+    }
+}
+
+/// The platform clock, as returned by [now](fn.now.html).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+impl Clock for SystemClock {
+    fn now(&self) -> SecondsSinceEpoch {
+        now()
+    }
+}
+
+// `SystemTime::now` panics on wasm32-unknown-unknown, so refuse to build a
+// clock from it there.
+#[cfg(all(
+    feature = "std",
+    not(feature = "js"),
+    target_arch = "wasm32",
+    target_os = "unknown"
+))]
+compile_error!(
+    "the system time is unavailable on wasm32-unknown-unknown: enable the `js` feature, \
+     or disable default features to build without a clock"
+);
+
+/// The current time according to the platform, in seconds since the epoch.
+#[cfg(all(feature = "js", target_arch = "wasm32"))]
+pub fn now() -> SecondsSinceEpoch {
+    (js_sys::Date::now() / 1000.0) as SecondsSinceEpoch
+}
+
+/// The current time according to the platform, in seconds since the epoch.
+#[cfg(all(feature = "std", not(all(feature = "js", target_arch = "wasm32"))))]
+pub fn now() -> SecondsSinceEpoch {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
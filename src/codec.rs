@@ -0,0 +1,254 @@
+//! Structured claims embedded in a token as compact binary blobs. An
+//! `EncodedClaim` is serialized as the base64url encoding of its value in the
+//! format of a `ClaimCodec`, which for large structures such as entitlements
+//! is much smaller than their JSON. [Bincode](struct.Bincode.html) and
+//! [Protobuf](struct.Protobuf.html) codecs are available with the `bincode`
+//! and `prost` features, and other formats can be plugged in by implementing
+//! `ClaimCodec`.
+//! ## Examples
+//! ```
+//! use jwt::codec::{EncodedClaim, Json};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//!     entitlements: EncodedClaim<Vec<u32>, Json>,
+//! }
+//!
+//! let claims = Claims {
+//!     sub: "someone".into(),
+//!     entitlements: EncodedClaim::new(vec![1, 2, 3]),
+//! };
+//! let json = serde_json::to_string(&claims).unwrap();
+//! assert_eq!(json, r#"{"sub":"someone","entitlements":"WzEsMiwzXQ"}"#);
+//!
+//! let claims: Claims = serde_json::from_str(&json).unwrap();
+//! assert_eq!(claims.entitlements.value, [1, 2, 3]);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned};
+use serde::ser;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::encoding;
+
+/// A binary format for the values of claims.
+pub trait ClaimCodec<T> {
+    type Error: fmt::Display;
+
+    fn encode(value: &T) -> Result<Vec<u8>, Self::Error>;
+
+    fn decode(bytes: &[u8]) -> Result<T, Self::Error>;
+}
+
+/// A claim holding `value`, serialized with the codec `E` and base64url
+/// encoded as a string.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncodedClaim<T, E> {
+    pub value: T,
+    codec: PhantomData<E>,
+}
+
+impl<T, E> EncodedClaim<T, E> {
+    pub fn new(value: T) -> Self {
+        EncodedClaim {
+            value,
+            codec: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, E> From<T> for EncodedClaim<T, E> {
+    fn from(value: T) -> Self {
+        EncodedClaim::new(value)
+    }
+}
+
+impl<T, E: ClaimCodec<T>> Serialize for EncodedClaim<T, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = E::encode(&self.value).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&encoding::encode(bytes))
+    }
+}
+
+impl<'de, T, E: ClaimCodec<T>> Deserialize<'de> for EncodedClaim<T, E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = encoding::decode(encoded).map_err(de::Error::custom)?;
+        E::decode(&bytes)
+            .map(EncodedClaim::new)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// JSON, for values that do not need to be smaller. Like every codec, this is
+/// only an encoding: anyone holding the token can decode the value. For
+/// claims that other readers must not see, use
+/// [Protected](../token/sealed/struct.Protected.html) with the `sealed`
+/// feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl<T: Serialize + DeserializeOwned> ClaimCodec<T> for Json {
+    type Error = serde_json::Error;
+
+    fn encode(value: &T) -> Result<Vec<u8>, Self::Error> {
+        serde_json::to_vec(value)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, Self::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+/// [Bincode](https://docs.rs/bincode), for any serde serializable value, with
+/// the `bincode` feature.
+#[cfg(feature = "bincode")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl<T: Serialize + DeserializeOwned> ClaimCodec<T> for Bincode {
+    type Error = bincode::Error;
+
+    fn encode(value: &T) -> Result<Vec<u8>, Self::Error> {
+        bincode::serialize(value)
+    }
+
+    /// Decode in the format of `bincode::serialize`, limited to the length
+    /// of the input, so that a length prefix in an untrusted claim can't
+    /// make it allocate more.
+    fn decode(bytes: &[u8]) -> Result<T, Self::Error> {
+        use bincode::Options;
+
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(bytes.len() as u64)
+            .deserialize(bytes)
+    }
+}
+
+/// Protocol Buffers, for messages generated by [prost](https://docs.rs/prost),
+/// with the `prost` feature.
+#[cfg(feature = "prost")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Protobuf;
+
+#[cfg(feature = "prost")]
+impl<T: prost::Message + Default> ClaimCodec<T> for Protobuf {
+    type Error = prost::DecodeError;
+
+    fn encode(value: &T) -> Result<Vec<u8>, Self::Error> {
+        Ok(value.encode_to_vec())
+    }
+
+    fn decode(bytes: &[u8]) -> Result<T, Self::Error> {
+        T::decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::codec::{EncodedClaim, Json};
+    use crate::error::Error;
+
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Entitlements {
+        products: Vec<u32>,
+        plan: String,
+    }
+
+    fn entitlements() -> Entitlements {
+        Entitlements {
+            products: (1000..1050).collect(),
+            plan: "enterprise".into(),
+        }
+    }
+
+    #[test]
+    pub fn json() -> Result<(), Error> {
+        let claim: EncodedClaim<Entitlements, Json> = entitlements().into();
+        let serialized = serde_json::to_value(&claim)?;
+        assert!(serialized.is_string());
+
+        let decoded: EncodedClaim<Entitlements, Json> = serde_json::from_value(serialized)?;
+        assert_eq!(decoded, claim);
+
+        let invalid: Result<EncodedClaim<Entitlements, Json>, _> =
+            serde_json::from_str(r#""not base64!""#);
+        assert!(invalid.is_err());
+        let wrong_type: Result<EncodedClaim<Entitlements, Json>, _> =
+            serde_json::from_str(r#""WzEsMiwzXQ""#);
+        assert!(wrong_type.is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    pub fn bincode() -> Result<(), Error> {
+        use std::collections::BTreeMap;
+
+        use crate::codec::{Bincode, ClaimCodec};
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::VerifyWithKey;
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let mut claims = BTreeMap::new();
+        claims.insert("ent", EncodedClaim::<_, Bincode>::new(entitlements()));
+        let token_str = claims.sign_with_key(&key)?;
+
+        let verified: BTreeMap<String, EncodedClaim<Entitlements, Bincode>> =
+            token_str.verify_with_key(&key)?;
+        assert_eq!(verified["ent"].value, entitlements());
+
+        // A vector claiming 2^40 elements, with none following.
+        let oversized = (1u64 << 40).to_le_bytes();
+        assert!(<Bincode as ClaimCodec<Vec<u64>>>::decode(&oversized).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "prost")]
+    #[test]
+    pub fn protobuf() -> Result<(), Error> {
+        use std::collections::BTreeMap;
+
+        use crate::codec::Protobuf;
+
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct Grants {
+            #[prost(uint32, repeated, tag = "1")]
+            products: Vec<u32>,
+            #[prost(string, tag = "2")]
+            plan: String,
+        }
+
+        let grants = Grants {
+            products: (1000..1050).collect(),
+            plan: "enterprise".into(),
+        };
+        let mut claims = BTreeMap::new();
+        claims.insert("ent", EncodedClaim::<_, Protobuf>::new(grants.clone()));
+        let json = serde_json::to_string(&claims)?;
+        assert!(json.len() < serde_json::to_string(&entitlements())?.len());
+
+        let decoded: BTreeMap<String, EncodedClaim<Grants, Protobuf>> =
+            serde_json::from_str(&json)?;
+        assert_eq!(decoded["ent"].value, grants);
+        Ok(())
+    }
+}
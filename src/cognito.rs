@@ -0,0 +1,372 @@
+//! Validation of tokens issued by [Amazon Cognito](https://docs.aws.amazon.com/cognito/latest/developerguide/amazon-cognito-user-pools-using-tokens-verifying-a-jwt.html)
+//! user pools, and of the `x-amzn-oidc-data` header that an AWS Application
+//! Load Balancer adds to requests it has authenticated.
+//!
+//! Cognito tokens name their kind in the `token_use` claim. ID tokens carry
+//! the app client in `aud`, while access tokens have no `aud` and carry it in
+//! `client_id` instead. The issuer is derived from the region and the user
+//! pool.
+//!
+//! Load balancer tokens are signed with ES256, pad their components, and
+//! keep `exp` and the ARN of the signing load balancer in the header. The
+//! public key is a PEM document at [alb_key_url](fn.alb_key_url.html), to
+//! be fetched with the application's HTTP client and loaded with
+//! `verifier_from_pem`.
+//! ## Examples
+//! ```
+//! use jwt::cognito::{CognitoValidation, TokenUse};
+//! use jwt::Claims;
+//! use serde_json::json;
+//!
+//! let validation = CognitoValidation::new("eu-west-1", "eu-west-1_AbCdEf", TokenUse::Access)
+//!     .client_id("3n4b5urk1ft4fl3mg5e62d9ado");
+//! assert_eq!(
+//!     validation.jwks_url(),
+//!     "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_AbCdEf/.well-known/jwks.json"
+//! );
+//!
+//! let claims: Claims = serde_json::from_value(json!({
+//!     "iss": "https://cognito-idp.eu-west-1.amazonaws.com/eu-west-1_AbCdEf",
+//!     "token_use": "access",
+//!     "client_id": "3n4b5urk1ft4fl3mg5e62d9ado",
+//!     "exp": 1302319100,
+//! }))
+//! .unwrap();
+//! assert!(validation.validate_at(&claims, 1302319000).is_ok());
+//! assert!(validation.validate_at(&claims, 1302319200).is_err());
+//! ```
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::AlgorithmType;
+use crate::claims::{Audience, Claims, SecondsSinceEpoch};
+use crate::encoding::Tolerant;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::Unverified;
+use crate::validation::Validation;
+use crate::Token;
+
+/// The kind of a Cognito token, from its `token_use` claim.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenUse {
+    Id,
+    Access,
+}
+
+/// The checks of a Cognito token: its issuer, its kind, and its app client,
+/// along with the checks of a `Validation`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CognitoValidation {
+    issuer: String,
+    token_use: TokenUse,
+    client_ids: BTreeSet<String>,
+    validation: Validation,
+}
+
+impl CognitoValidation {
+    /// Accept tokens of `token_use` from the user pool `user_pool_id` in
+    /// `region`.
+    pub fn new(region: &str, user_pool_id: &str, token_use: TokenUse) -> Self {
+        let issuer = alloc::format!(
+            "https://cognito-idp.{}.amazonaws.com/{}",
+            region,
+            user_pool_id
+        );
+        CognitoValidation {
+            validation: Validation::new().issuer(issuer.as_str()),
+            issuer,
+            token_use,
+            client_ids: BTreeSet::new(),
+        }
+    }
+
+    /// Accept tokens issued to the app client `client_id`. When any are set,
+    /// the `aud` of ID tokens or the `client_id` of access tokens must match
+    /// one of them.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_ids.insert(client_id.into());
+        self
+    }
+
+    /// Run the checks of `validation` as well, e.g. for leeway or required
+    /// claims. Its issuers are replaced by the user pool.
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.validation = validation
+            .issuer_policy(Default::default())
+            .issuer(self.issuer.as_str());
+        self
+    }
+
+    /// The issuer of the user pool's tokens.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// The URL of the JWK set of the user pool.
+    pub fn jwks_url(&self) -> String {
+        alloc::format!("{}/.well-known/jwks.json", self.issuer)
+    }
+
+    /// Check the claims against `now`, in seconds since the epoch. The
+    /// header checks of the `Validation` only run when this is used as a
+    /// `ClaimsValidator`.
+    pub fn validate_at(&self, claims: &Claims, now: SecondsSinceEpoch) -> Result<(), Error> {
+        self.validation.check_claims_at(claims.as_ref(), now)?;
+
+        match claims.get_private::<TokenUse>("token_use") {
+            Ok(Some(token_use)) if token_use == self.token_use => (),
+            Ok(None) => return Err(Error::MissingClaim("token_use".into())),
+            _ => return Err(Error::InvalidClaim("token_use".into())),
+        }
+
+        if self.client_ids.is_empty() {
+            return Ok(());
+        }
+        let (name, client_id) = match self.token_use {
+            TokenUse::Id => ("aud", claims.registered.audience.clone()),
+            TokenUse::Access => (
+                "client_id",
+                claims
+                    .get_private::<String>("client_id")?
+                    .map(Audience::from),
+            ),
+        };
+        match client_id {
+            Some(client_id) if client_id.iter().any(|id| self.client_ids.contains(id)) => Ok(()),
+            Some(_) => Err(Error::InvalidClaim(name.into())),
+            None => Err(Error::MissingClaim(name.into())),
+        }
+    }
+}
+
+/// Validation of the header, and of the claims against the platform clock.
+#[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+impl<H: JoseHeader> crate::validation::ClaimsValidator<Claims, H> for CognitoValidation {
+    fn validate(&self, header: &H, claims: &Claims) -> Result<(), Error> {
+        self.validation.validate_header(header)?;
+        self.validate_at(claims, crate::clock::now())
+    }
+}
+
+/// The name of the header holding the token of a load balancer.
+pub const ALB_DATA_HEADER: &str = "x-amzn-oidc-data";
+
+/// The URL of the PEM encoded public key `key_id` of the load balancers in
+/// `region`.
+pub fn alb_key_url(region: &str, key_id: &str) -> String {
+    alloc::format!(
+        "https://public-keys.auth.elb.{}.amazonaws.com/{}",
+        region,
+        key_id
+    )
+}
+
+/// The header of a load balancer token.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AlbHeader {
+    #[serde(rename = "alg")]
+    pub algorithm: AlgorithmType,
+    #[serde(rename = "kid")]
+    pub key_id: String,
+    /// The ARN of the load balancer that signed the token.
+    pub signer: String,
+    /// The issuer of the identity provider that authenticated the user.
+    #[serde(rename = "iss")]
+    pub issuer: String,
+    /// The client id of the load balancer at the identity provider.
+    pub client: String,
+    #[serde(rename = "exp")]
+    pub expiration: SecondsSinceEpoch,
+}
+
+impl JoseHeader for AlbHeader {
+    fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        Some(&self.key_id)
+    }
+}
+
+impl AlbHeader {
+    /// Check that the token was signed by the load balancer `signer`, an
+    /// ARN, with ES256, and has not expired at `now`.
+    pub fn validate_at(&self, signer: &str, now: SecondsSinceEpoch) -> Result<(), Error> {
+        if self.algorithm != AlgorithmType::Es256 {
+            return Err(Error::AlgorithmMismatch(
+                self.algorithm,
+                AlgorithmType::Es256,
+            ));
+        }
+        if self.signer != signer {
+            return Err(Error::InvalidClaim("signer".into()));
+        }
+        if now >= self.expiration {
+            return Err(Error::Expired);
+        }
+        Ok(())
+    }
+}
+
+/// Parse the value of the `x-amzn-oidc-data` header, tolerating its padded
+/// components. The token must still be verified, with the key of
+/// `alb_key_url(region, &token.header().key_id)`, and its header validated.
+pub fn parse_alb_data(value: &str) -> Result<Token<AlbHeader, Claims, Unverified<'_>>, Error> {
+    Token::parse_unverified_with(value.trim(), &Tolerant)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::Claims;
+    use crate::cognito::{CognitoValidation, TokenUse};
+    use crate::error::Error;
+    use crate::validation::Validation;
+
+    const ISSUER: &str = "https://cognito-idp.us-east-1.amazonaws.com/us-east-1_pool";
+
+    fn claims(token_use: &str) -> Claims {
+        serde_json::from_value(json!({
+            "iss": ISSUER,
+            "sub": "someone",
+            "token_use": token_use,
+            "aud": "id-client",
+            "client_id": "access-client",
+            "exp": 2000,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    pub fn token_use_and_client() -> Result<(), Error> {
+        let id = CognitoValidation::new("us-east-1", "us-east-1_pool", TokenUse::Id)
+            .client_id("id-client");
+        let access = CognitoValidation::new("us-east-1", "us-east-1_pool", TokenUse::Access)
+            .client_id("access-client");
+        assert_eq!(id.issuer(), ISSUER);
+
+        id.validate_at(&claims("id"), 1000)?;
+        access.validate_at(&claims("access"), 1000)?;
+
+        match id.validate_at(&claims("access"), 1000) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "token_use"),
+            other => panic!(
+                "Access token should not be accepted as ID token: {:?}",
+                other
+            ),
+        }
+        let mut other_client = claims("access");
+        other_client
+            .private
+            .insert("client_id".into(), "id-client".into());
+        match access.validate_at(&other_client, 1000) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "client_id"),
+            other => panic!(
+                "Token of another client should not be accepted: {:?}",
+                other
+            ),
+        }
+        let mut no_token_use = claims("id");
+        no_token_use.private.remove("token_use");
+        match id.validate_at(&no_token_use, 1000) {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "token_use"),
+            other => panic!(
+                "Token without token_use should not be accepted: {:?}",
+                other
+            ),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn issuer_and_validation() {
+        let validation = CognitoValidation::new("us-east-1", "us-east-1_pool", TokenUse::Id)
+            .validation(
+                Validation::new()
+                    .issuer("https://other.example.com")
+                    .leeway(60),
+            );
+
+        assert!(validation.validate_at(&claims("id"), 2030).is_ok());
+        let mut other_pool = claims("id");
+        other_pool.registered.issuer =
+            Some("https://cognito-idp.us-east-1.amazonaws.com/other".into());
+        match validation.validate_at(&other_pool, 1000) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "iss"),
+            other => panic!("Token of another pool should not be accepted: {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    pub fn alb_data() -> Result<(), Error> {
+        use crate::algorithm::openssl::PKeyWithDigest;
+        use crate::algorithm::{verifier_from_pem, AlgorithmType, SigningAlgorithm};
+        use crate::cognito::{alb_key_url, parse_alb_data};
+        use crate::token::Verified;
+        use crate::{ToBase64, Token, VerifyWithKey};
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+
+        const SIGNER: &str = "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/app/web/50dc6c495c0c9188";
+        let header = json!({
+            "alg": "ES256",
+            "kid": "12345678-1234-1234-1234-123456789012",
+            "signer": SIGNER,
+            "iss": "https://accounts.example.com",
+            "client": "alb-client",
+            "exp": 2000,
+        });
+        let claims = json!({"sub": "someone", "email": "someone@example.com"});
+        let pad =
+            |component: &str| format!("{}{}", component, "=".repeat((4 - component.len() % 4) % 4));
+        let header_str = pad(&header.to_base64()?);
+        let claims_str = pad(&claims.to_base64()?);
+
+        let key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../test/es256-private.pem"))?,
+        };
+        let signature = key.sign(&header_str, &claims_str)?;
+        let value = format!("{}.{}.{}", header_str, claims_str, pad(&signature));
+
+        let token = parse_alb_data(&value)?;
+        assert_eq!(
+            alb_key_url("us-east-1", &token.header().key_id),
+            "https://public-keys.auth.elb.us-east-1.amazonaws.com/12345678-1234-1234-1234-123456789012"
+        );
+        let verifier = verifier_from_pem(
+            include_bytes!("../test/es256-public.pem"),
+            AlgorithmType::Es256,
+        )?;
+        let token: Token<_, Claims, Verified> = token.verify_with_key(&verifier)?;
+        token.header().validate_at(SIGNER, 1000)?;
+        assert_eq!(
+            token.claims().registered.subject.as_deref(),
+            Some("someone")
+        );
+
+        match token
+            .header()
+            .validate_at("arn:aws:elasticloadbalancing:other", 1000)
+        {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "signer"),
+            other => panic!(
+                "Token of another load balancer should not be accepted: {:?}",
+                other
+            ),
+        }
+        match token.header().validate_at(SIGNER, 2000) {
+            Err(Error::Expired) => (),
+            other => panic!("Expired token should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+}
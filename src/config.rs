@@ -0,0 +1,280 @@
+//! Keys declared in configuration files. A `KeyConfig` deserializes from any
+//! serde format, e.g. JSON or TOML, and names the algorithm and where the key
+//! material comes from. Loading it produces boxed signers and verifiers, or a
+//! whole store when several keys are configured.
+//! ## Examples
+//! ```
+//! use jwt::config::{self, KeyConfig};
+//! use jwt::{SignWithStore, Store, VerifyWithStore};
+//! use std::collections::BTreeMap;
+//!
+//! std::env::set_var("EXAMPLE_JWT_SECRET", format!("base64:{}", "c2VjcmV0".repeat(8)));
+//! let configs: Vec<KeyConfig> = serde_json::from_str(r#"[
+//!     {"key_id": "first", "algorithm": "HS256", "secret": "a-first-secret-of-at-least-32-bytes"},
+//!     {"key_id": "second", "algorithm": "HS384", "env": "EXAMPLE_JWT_SECRET"}
+//! ]"#).unwrap();
+//!
+//! let signers = config::signing_store(&configs).unwrap();
+//! let verifiers = config::verifying_store(&configs).unwrap();
+//!
+//! let mut claims = BTreeMap::new();
+//! claims.insert("sub", "someone");
+//! let token_str = ("second", claims).sign_with_store(&signers).unwrap();
+//! let claims: BTreeMap<String, String> = token_str.verify_with_store(&verifiers).unwrap();
+//! assert_eq!(claims["sub"], "someone");
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::algorithm::secret::HmacKeyFromEnv;
+use crate::algorithm::store::PinnedStore;
+use crate::algorithm::{self, AlgorithmType, SigningAlgorithm, VerifyingAlgorithm};
+use crate::error::Error;
+
+/// Where the key material of a `KeyConfig` is read from.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// A shared secret written inline in the configuration. Like secrets
+    /// read from the environment, it must be at least as long as the hash
+    /// output of the algorithm.
+    Secret(String),
+    /// The name of an environment variable holding a shared secret, which
+    /// may be base64 or hex encoded as read by
+    /// [HmacKeyFromEnv](../algorithm/secret/struct.HmacKeyFromEnv.html).
+    Env(String),
+    /// The path of a PEM encoded RSA or EC key. Requires the `openssl`
+    /// feature.
+    PemFile(PathBuf),
+}
+
+impl fmt::Debug for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeySource::Secret(_) => f.write_str("Secret(<redacted>)"),
+            KeySource::Env(name) => f.debug_tuple("Env").field(name).finish(),
+            KeySource::PemFile(path) => f.debug_tuple("PemFile").field(path).finish(),
+        }
+    }
+}
+
+/// A key as declared in configuration. The source is flattened into the
+/// config, so it is given as one of the fields `secret`, `env`, or
+/// `pem_file`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct KeyConfig {
+    #[serde(default)]
+    pub key_id: Option<String>,
+    pub algorithm: AlgorithmType,
+    #[serde(flatten)]
+    pub source: KeySource,
+}
+
+impl KeyConfig {
+    pub fn new(algorithm: AlgorithmType, source: KeySource) -> Self {
+        KeyConfig {
+            key_id: None,
+            algorithm,
+            source,
+        }
+    }
+
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Load the key for signing tokens. Shared secrets from any source fail
+    /// with `Error::WeakKey` if they are shorter than the hash output.
+    pub fn signer(&self) -> Result<Box<dyn SigningAlgorithm + Send + Sync>, Error> {
+        match &self.source {
+            KeySource::Secret(secret) => Ok(Box::new(self.secret_key(secret.as_bytes())?)),
+            KeySource::Env(name) => {
+                Ok(Box::new(HmacKeyFromEnv::new(name).key_for(self.algorithm)?))
+            }
+            KeySource::PemFile(path) => pem_signer(&std::fs::read(path)?, self.algorithm),
+        }
+    }
+
+    /// Load the key for verifying tokens.
+    pub fn verifier(&self) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+        match &self.source {
+            KeySource::Secret(secret) => Ok(Box::new(self.secret_key(secret.as_bytes())?)),
+            KeySource::Env(name) => {
+                Ok(Box::new(HmacKeyFromEnv::new(name).key_for(self.algorithm)?))
+            }
+            KeySource::PemFile(path) => pem_verifier(&std::fs::read(path)?, self.algorithm),
+        }
+    }
+
+    fn secret_key(&self, secret: &[u8]) -> Result<Box<dyn algorithm::Algorithm>, Error> {
+        algorithm::from_secret_strict(secret, self.algorithm)
+    }
+
+    fn required_key_id(&self) -> Result<&str, Error> {
+        self.key_id.as_deref().ok_or(Error::NoKeyId)
+    }
+}
+
+/// Load every configured key for signing, each pinned to its configured
+/// algorithm. Every config must have a key id.
+pub fn signing_store(
+    configs: &[KeyConfig],
+) -> Result<PinnedStore<Box<dyn SigningAlgorithm + Send + Sync>>, Error> {
+    let mut store = PinnedStore::new();
+    for config in configs {
+        store.insert(
+            config.required_key_id()?,
+            config.algorithm,
+            config.signer()?,
+        );
+    }
+    Ok(store)
+}
+
+/// Load every configured key for verifying, each pinned to its configured
+/// algorithm. Every config must have a key id.
+pub fn verifying_store(
+    configs: &[KeyConfig],
+) -> Result<PinnedStore<Box<dyn VerifyingAlgorithm + Send + Sync>>, Error> {
+    let mut store = PinnedStore::new();
+    for config in configs {
+        store.insert(
+            config.required_key_id()?,
+            config.algorithm,
+            config.verifier()?,
+        );
+    }
+    Ok(store)
+}
+
+#[cfg(feature = "openssl")]
+fn pem_signer(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn SigningAlgorithm + Send + Sync>, Error> {
+    algorithm::signer_from_pem(pem, algorithm)
+}
+
+#[cfg(not(feature = "openssl"))]
+fn pem_signer(
+    _: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn SigningAlgorithm + Send + Sync>, Error> {
+    Err(Error::UnsupportedAlgorithm(algorithm))
+}
+
+#[cfg(feature = "openssl")]
+fn pem_verifier(
+    pem: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    algorithm::verifier_from_pem(pem, algorithm)
+}
+
+#[cfg(not(feature = "openssl"))]
+fn pem_verifier(
+    _: &[u8],
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    Err(Error::UnsupportedAlgorithm(algorithm))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::AlgorithmType;
+    use crate::config::{verifying_store, KeyConfig, KeySource};
+    use crate::error::Error;
+    use crate::{SignWithKey, Store};
+
+    #[test]
+    pub fn deserialize() -> Result<(), Error> {
+        let config: KeyConfig = serde_json::from_str(
+            r#"{"key_id": "main", "algorithm": "RS256", "pem_file": "keys/main.pem"}"#,
+        )?;
+        let expected = KeyConfig::new(
+            AlgorithmType::Rs256,
+            KeySource::PemFile("keys/main.pem".into()),
+        )
+        .key_id("main");
+        assert_eq!(config, expected);
+
+        let config: KeyConfig =
+            serde_json::from_str(r#"{"algorithm": "HS256", "secret": "hunter2"}"#)?;
+        assert_eq!(config.key_id, None);
+        assert!(!format!("{:?}", config).contains("hunter2"));
+        Ok(())
+    }
+
+    #[test]
+    pub fn load() -> Result<(), Error> {
+        std::env::set_var("JWT_CONFIG_TEST_SECRET", "a-secret-which-is-32-bytes-long!");
+        let from_env = KeyConfig::new(
+            AlgorithmType::Hs256,
+            KeySource::Env("JWT_CONFIG_TEST_SECRET".into()),
+        );
+        let inline = KeyConfig::new(
+            AlgorithmType::Hs256,
+            KeySource::Secret("a-secret-which-is-32-bytes-long!".into()),
+        );
+        let token_str = "claims".sign_with_key(&from_env.signer()?)?;
+        assert_eq!(token_str, "claims".sign_with_key(&inline.signer()?)?);
+
+        let missing = KeyConfig::new(
+            AlgorithmType::Hs256,
+            KeySource::Env("JWT_CONFIG_TEST_MISSING".into()),
+        );
+        assert!(matches!(missing.signer(), Err(Error::Io(_))));
+
+        match verifying_store(std::slice::from_ref(&inline)) {
+            Err(Error::NoKeyId) => (),
+            other => panic!("Expected a missing key id: {:?}", other.is_ok()),
+        }
+        let store = verifying_store(&[inline.key_id("main")])?;
+        assert!(store.get_for("main", AlgorithmType::Hs256).is_some());
+        assert!(store.get_for("main", AlgorithmType::Hs512).is_none());
+        Ok(())
+    }
+
+    #[test]
+    pub fn weak_secrets() {
+        std::env::set_var("JWT_CONFIG_TEST_WEAK_SECRET", "secret");
+        let sources = [
+            KeySource::Secret("secret".into()),
+            KeySource::Env("JWT_CONFIG_TEST_WEAK_SECRET".into()),
+        ];
+        for source in sources {
+            let config = KeyConfig::new(AlgorithmType::Hs256, source);
+            match config.signer() {
+                Err(Error::WeakKey(AlgorithmType::Hs256)) => (),
+                other => panic!("Expected a weak signing key: {:?}", other.is_ok()),
+            }
+            match config.verifier() {
+                Err(Error::WeakKey(AlgorithmType::Hs256)) => (),
+                other => panic!("Expected a weak key: {:?}", other.is_ok()),
+            }
+        }
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    pub fn load_pem() -> Result<(), Error> {
+        let private = KeyConfig::new(
+            AlgorithmType::Es256,
+            KeySource::PemFile("test/es256-private.pem".into()),
+        );
+        let public = KeyConfig::new(
+            AlgorithmType::Es256,
+            KeySource::PemFile("test/es256-public.pem".into()),
+        );
+        let signer = private.signer()?;
+        let verifier = public.verifier()?;
+
+        let signature = signer.sign("header", "claims")?;
+        assert!(verifier.verify("header", "claims", &signature)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! The [`cnf` claim](https://tools.ietf.org/html/rfc7800), binding a token to
+//! a key that its presenter must prove possession of, e.g. through mutual
+//! TLS or a DPoP proof. Once the presenter has proven possession of a key,
+//! check that it is the key the token was issued for.
+//! ## Examples
+//! ```
+//! use jwt::confirmation::{Confirmation, ProofOfPossession};
+//! use jwt::{Claims, FromBase64};
+//!
+//! // {"sub":"someone","cnf":{"x5t#S256":"bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2"}}
+//! let claims = Claims::from_base64(
+//!     "eyJzdWIiOiJzb21lb25lIiwiY25mIjp7Ing1dCNTMjU2IjoiYndjSzBlc2MzQUNDM0RCMlk1X2xFU3NYRThvOWx0YzA1Tzg5amROLWRnMiJ9fQ",
+//! )
+//! .unwrap();
+//!
+//! let confirmation = claims.confirmation().unwrap().unwrap();
+//! assert_eq!(
+//!     confirmation,
+//!     Confirmation::X509Sha256("bwcK0esc3ACC3DB2Y5_lESsXE8o9ltc05O89jdN-dg2".into())
+//! );
+//!
+//! let proof = ProofOfPossession::Thumbprint("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs".into());
+//! assert!(proof.check(Some(&confirmation)).is_err());
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::access_token::AccessTokenClaims;
+use crate::claims::Claims;
+use crate::encoding;
+use crate::error::Error;
+use crate::validation::ClaimsValidator;
+
+/// How a token is bound to a key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Confirmation {
+    /// The key itself, as a JWK.
+    #[serde(rename = "jwk")]
+    Jwk(Map<String, Value>),
+    /// The [RFC 7638](https://tools.ietf.org/html/rfc7638) thumbprint of the
+    /// key, as used by DPoP.
+    #[serde(rename = "jkt")]
+    JwkThumbprint(String),
+    /// The SHA-256 thumbprint of an X.509 certificate, as used by mutual TLS.
+    #[serde(rename = "x5t#S256")]
+    X509Sha256(String),
+}
+
+impl Confirmation {
+    /// The thumbprint of the confirmation key, if it is bound by JWK.
+    pub fn jwk_thumbprint(&self) -> Result<Option<String>, Error> {
+        match self {
+            Confirmation::Jwk(jwk) => Ok(Some(jwk_thumbprint(jwk)?)),
+            Confirmation::JwkThumbprint(thumbprint) => Ok(Some(thumbprint.clone())),
+            Confirmation::X509Sha256(_) => Ok(None),
+        }
+    }
+}
+
+/// The [RFC 7638](https://tools.ietf.org/html/rfc7638) thumbprint of a JWK:
+/// the SHA-256 digest of its required members, base64 encoded.
+pub fn jwk_thumbprint(jwk: &Map<String, Value>) -> Result<String, Error> {
+    let members: &[&str] = match jwk.get("kty").and_then(Value::as_str) {
+        Some("EC") => &["crv", "kty", "x", "y"],
+        Some("OKP") => &["crv", "kty", "x"],
+        Some("RSA") => &["e", "kty", "n"],
+        Some("oct") => &["k", "kty"],
+        _ => return Err(Error::Format),
+    };
+
+    // The members are in lexicographic order, and serde_json writes strings
+    // without insignificant whitespace, as the thumbprint requires.
+    let mut required = Map::new();
+    for member in members {
+        match jwk.get(*member) {
+            Some(value @ Value::String(_)) => {
+                required.insert((*member).into(), value.clone());
+            }
+            _ => return Err(Error::Format),
+        }
+    }
+
+    let digest = Sha256::digest(serde_json::to_vec(&required)?);
+    Ok(encoding::encode(digest))
+}
+
+/// A key that the presenter of a token has proven possession of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofOfPossession {
+    /// The RFC 7638 thumbprint of a key, e.g. from a verified DPoP proof.
+    Thumbprint(String),
+    /// A DER encoded client certificate, e.g. from a mutual TLS connection.
+    Certificate(Vec<u8>),
+}
+
+impl ProofOfPossession {
+    /// Check that the token was issued for the proven key.
+    pub fn check(&self, confirmation: Option<&Confirmation>) -> Result<(), Error> {
+        let confirmation = confirmation.ok_or_else(|| Error::MissingClaim("cnf".into()))?;
+        let confirmed = match (self, confirmation) {
+            (ProofOfPossession::Thumbprint(thumbprint), _) => {
+                confirmation.jwk_thumbprint()?.as_ref() == Some(thumbprint)
+            }
+            (ProofOfPossession::Certificate(der), Confirmation::X509Sha256(expected)) => {
+                let digest = Sha256::digest(der);
+                encoding::encode(digest) == *expected
+            }
+            (ProofOfPossession::Certificate(_), _) => false,
+        };
+
+        if confirmed {
+            Ok(())
+        } else {
+            Err(Error::InvalidClaim("cnf".into()))
+        }
+    }
+}
+
+impl<H> ClaimsValidator<Claims, H> for ProofOfPossession {
+    fn validate(&self, _header: &H, claims: &Claims) -> Result<(), Error> {
+        self.check(claims.confirmation()?.as_ref())
+    }
+}
+
+impl<H> ClaimsValidator<AccessTokenClaims, H> for ProofOfPossession {
+    fn validate(&self, _header: &H, claims: &AccessTokenClaims) -> Result<(), Error> {
+        self.check(claims.confirmation.as_ref())
+    }
+}
+
+impl Claims {
+    /// The `cnf` claim, if it is present.
+    pub fn confirmation(&self) -> Result<Option<Confirmation>, Error> {
+        self.get_private("cnf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::Claims;
+    use crate::confirmation::{jwk_thumbprint, Confirmation, ProofOfPossession};
+    use crate::error::Error;
+
+    #[test]
+    pub fn rfc7638_thumbprint() -> Result<(), Error> {
+        // The example key from RFC 7638, section 3.1.
+        let jwk = json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29"
+        });
+        let jwk = jwk.as_object().unwrap();
+        assert_eq!(
+            jwk_thumbprint(jwk)?,
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+
+        let thumbprint = ProofOfPossession::Thumbprint(jwk_thumbprint(jwk)?);
+        thumbprint.check(Some(&Confirmation::Jwk(jwk.clone())))?;
+        thumbprint.check(Some(&Confirmation::JwkThumbprint(
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs".into(),
+        )))?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn certificate_binding() -> Result<(), Error> {
+        let mut claims = Claims::default();
+        claims.set_private(
+            "cnf",
+            json!({ "x5t#S256": "LPJNul-wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ" }),
+        )?;
+        let confirmation = claims.confirmation()?;
+
+        ProofOfPossession::Certificate(b"hello".to_vec()).check(confirmation.as_ref())?;
+
+        match ProofOfPossession::Certificate(b"other".to_vec()).check(confirmation.as_ref()) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "cnf"),
+            other => panic!("Wrong certificate should not be accepted: {:?}", other),
+        }
+
+        match ProofOfPossession::Certificate(b"hello".to_vec()).check(None) {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "cnf"),
+            other => panic!("Unbound token should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+}
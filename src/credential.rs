@@ -0,0 +1,455 @@
+//! Claims of [JWT encoded verifiable credentials and presentations](https://www.w3.org/TR/vc-data-model/#json-web-token),
+//! carried in the `vc` and `vp` claims. Properties of a credential that have
+//! a registered claim counterpart, such as its issuer and id, must agree
+//! with that claim, which `check` verifies once the token itself has been
+//! verified.
+//! ## Examples
+//! ```
+//! use jwt::credential::CredentialClaims;
+//! use jwt::FromBase64;
+//!
+//! // {"iss":"did:example:issuer","sub":"did:example:holder","nbf":1262304000,
+//! //  "jti":"http://example.edu/credentials/3732",
+//! //  "vc":{"@context":["https://www.w3.org/2018/credentials/v1"],
+//! //        "type":["VerifiableCredential","UniversityDegreeCredential"],
+//! //        "credentialSubject":{"degree":"Bachelor of Science"}}}
+//! let claims = CredentialClaims::from_base64("eyJpc3MiOiJkaWQ6ZXhhbXBsZTppc3N1ZXIiLCJzdWIiOiJkaWQ6ZXhhbXBsZTpob2xkZXIiLCJuYmYiOjEyNjIzMDQwMDAsImp0aSI6Imh0dHA6Ly9leGFtcGxlLmVkdS9jcmVkZW50aWFscy8zNzMyIiwidmMiOnsiQGNvbnRleHQiOlsiaHR0cHM6Ly93d3cudzMub3JnLzIwMTgvY3JlZGVudGlhbHMvdjEiXSwidHlwZSI6WyJWZXJpZmlhYmxlQ3JlZGVudGlhbCIsIlVuaXZlcnNpdHlEZWdyZWVDcmVkZW50aWFsIl0sImNyZWRlbnRpYWxTdWJqZWN0Ijp7ImRlZ3JlZSI6IkJhY2hlbG9yIG9mIFNjaWVuY2UifX19").unwrap();
+//!
+//! claims.check().unwrap();
+//! assert!(claims.credential.has_type("UniversityDegreeCredential"));
+//! assert_eq!(claims.credential.credential_subject["degree"], "Bachelor of Science");
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::error::Error;
+
+const CREDENTIAL_TYPE: &str = "VerifiableCredential";
+const PRESENTATION_TYPE: &str = "VerifiablePresentation";
+
+/// The issuer of a credential, either its id or an object with an id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CredentialIssuer {
+    Id(String),
+    Object {
+        id: String,
+        #[serde(flatten)]
+        properties: BTreeMap<String, Value>,
+    },
+}
+
+impl CredentialIssuer {
+    pub fn id(&self) -> &str {
+        match self {
+            CredentialIssuer::Id(id) => id,
+            CredentialIssuer::Object { id, .. } => id,
+        }
+    }
+}
+
+/// The `vc` claim. Properties represented by registered claims, such as
+/// `id` and `issuer`, are usually left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Credential {
+    #[serde(rename = "@context")]
+    pub context: Vec<Value>,
+
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<CredentialIssuer>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuance_date: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<String>,
+
+    pub credential_subject: Value,
+
+    #[serde(flatten)]
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl Credential {
+    pub fn has_type(&self, type_: &str) -> bool {
+        self.types.iter().any(|t| t == type_)
+    }
+
+    /// The id of the credential subject, if there is a single subject.
+    pub fn subject_id(&self) -> Option<&str> {
+        self.credential_subject.get("id").and_then(Value::as_str)
+    }
+}
+
+/// The `vp` claim.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Presentation {
+    #[serde(rename = "@context")]
+    pub context: Vec<Value>,
+
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holder: Option<String>,
+
+    /// Credentials, either as compact JWTs or embedded objects.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verifiable_credential: Vec<Value>,
+
+    #[serde(flatten)]
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl Presentation {
+    pub fn has_type(&self, type_: &str) -> bool {
+        self.types.iter().any(|t| t == type_)
+    }
+
+    /// The credentials of the presentation that are compact JWTs.
+    pub fn credential_tokens(&self) -> impl Iterator<Item = &str> {
+        self.verifiable_credential.iter().filter_map(Value::as_str)
+    }
+}
+
+/// The claims of a JWT encoded verifiable credential.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialClaims {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+
+    #[serde(rename = "vc")]
+    pub credential: Credential,
+
+    #[serde(flatten)]
+    pub private: BTreeMap<String, Value>,
+}
+
+impl CredentialClaims {
+    /// Check the credential against the registered claims: `iss` is
+    /// required and is the issuer, `nbf` is required and is the issuance
+    /// date, `sub` is the subject id, `jti` is the credential id, and `exp`
+    /// is the expiration date.
+    pub fn check(&self) -> Result<(), Error> {
+        let registered = &self.registered;
+        let credential = &self.credential;
+        if !credential.has_type(CREDENTIAL_TYPE) {
+            return Err(Error::InvalidClaim("vc".into()));
+        }
+
+        let issuer = registered
+            .issuer
+            .as_deref()
+            .ok_or_else(|| Error::MissingClaim("iss".into()))?;
+        if let Some(ref credential_issuer) = credential.issuer {
+            check_equal(Some(credential_issuer.id()), issuer, "iss")?;
+        }
+
+        let not_before = registered
+            .not_before
+            .ok_or_else(|| Error::MissingClaim("nbf".into()))?;
+        if let Some(ref issuance_date) = credential.issuance_date {
+            check_time(issuance_date, Some(not_before), "nbf")?;
+        }
+
+        if let Some(subject_id) = credential.subject_id() {
+            check_present(registered.subject.as_deref(), subject_id, "sub")?;
+        }
+        if let Some(ref id) = credential.id {
+            check_present(registered.json_web_token_id.as_deref(), id, "jti")?;
+        }
+        if let Some(ref expiration_date) = credential.expiration_date {
+            check_time(expiration_date, registered.expiration, "exp")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRef<RegisteredClaims> for CredentialClaims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+}
+
+/// The claims of a JWT encoded verifiable presentation.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresentationClaims {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+
+    #[serde(rename = "vp")]
+    pub presentation: Presentation,
+
+    /// A challenge from the verifier, to prevent replay.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+
+    #[serde(flatten)]
+    pub private: BTreeMap<String, Value>,
+}
+
+impl PresentationClaims {
+    /// Check the presentation against the registered claims: `iss` is
+    /// required and is the holder, and `jti` is the presentation id.
+    pub fn check(&self) -> Result<(), Error> {
+        let presentation = &self.presentation;
+        if !presentation.has_type(PRESENTATION_TYPE) {
+            return Err(Error::InvalidClaim("vp".into()));
+        }
+
+        let issuer = self
+            .registered
+            .issuer
+            .as_deref()
+            .ok_or_else(|| Error::MissingClaim("iss".into()))?;
+        if let Some(ref holder) = presentation.holder {
+            check_equal(Some(holder), issuer, "iss")?;
+        }
+        if let Some(ref id) = presentation.id {
+            check_present(self.registered.json_web_token_id.as_deref(), id, "jti")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRef<RegisteredClaims> for PresentationClaims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+}
+
+fn check_equal(expected: Option<&str>, value: &str, name: &str) -> Result<(), Error> {
+    if expected == Some(value) {
+        Ok(())
+    } else {
+        Err(Error::InvalidClaim(name.into()))
+    }
+}
+
+fn check_present(claim: Option<&str>, expected: &str, name: &str) -> Result<(), Error> {
+    match claim {
+        Some(value) => check_equal(Some(expected), value, name),
+        None => Err(Error::MissingClaim(name.into())),
+    }
+}
+
+fn check_time(date: &str, claim: Option<SecondsSinceEpoch>, name: &str) -> Result<(), Error> {
+    let claim = claim.ok_or_else(|| Error::MissingClaim(name.into()))?;
+    match parse_date_time(date) {
+        Some(time) if time == claim => Ok(()),
+        _ => Err(Error::InvalidClaim(name.into())),
+    }
+}
+
+/// Parse an RFC 3339 date-time, such as `2010-01-01T19:23:24Z`, into
+/// seconds since the epoch, ignoring fractional seconds.
+fn parse_date_time(date: &str) -> Option<SecondsSinceEpoch> {
+    let bytes = date.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+
+    let number = |range| number_in(date, range);
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &date[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let end = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        rest = &fraction[end..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            sign * (number_in(rest, 1..3)? * 3600 + number_in(rest, 4..6)? * 60)
+        }
+    };
+
+    // Days from the civil date, per Howard Hinnant's algorithm.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let seconds = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    SecondsSinceEpoch::try_from(seconds).ok()
+}
+
+fn number_in(s: &str, range: Range<usize>) -> Option<i64> {
+    let digits = s.get(range)?;
+    if digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::RegisteredClaims;
+    use crate::credential::{
+        parse_date_time, Credential, CredentialClaims, CredentialIssuer, Presentation,
+        PresentationClaims,
+    };
+    use crate::error::Error;
+    use crate::{FromBase64, ToBase64};
+
+    fn claims() -> CredentialClaims {
+        CredentialClaims {
+            registered: RegisteredClaims {
+                issuer: Some("did:example:issuer".into()),
+                subject: Some("did:example:holder".into()),
+                not_before: Some(1262373804),
+                expiration: Some(1577906604),
+                json_web_token_id: Some("urn:uuid:1".into()),
+                ..Default::default()
+            },
+            credential: Credential {
+                context: vec![json!("https://www.w3.org/2018/credentials/v1")],
+                types: vec!["VerifiableCredential".into()],
+                id: Some("urn:uuid:1".into()),
+                issuer: Some(CredentialIssuer::Object {
+                    id: "did:example:issuer".into(),
+                    properties: Default::default(),
+                }),
+                issuance_date: Some("2010-01-01T19:23:24Z".into()),
+                expiration_date: Some("2020-01-01T20:23:24+01:00".into()),
+                credential_subject: json!({ "id": "did:example:holder" }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn date_times() {
+        assert_eq!(parse_date_time("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_date_time("2010-01-01T19:23:24.123Z"),
+            Some(1262373804)
+        );
+        assert_eq!(
+            parse_date_time("2000-02-29T01:00:00+01:00"),
+            Some(951782400)
+        );
+        assert_eq!(parse_date_time("2010-01-01"), None);
+        assert_eq!(parse_date_time("2010-13-01T00:00:00Z"), None);
+        assert_eq!(parse_date_time("1970-01-01T00:00:00+0é00"), None);
+        assert_eq!(parse_date_time("1970-01-01T00:00:00+é:00"), None);
+    }
+
+    #[test]
+    pub fn credential_correspondence() -> Result<(), Error> {
+        let claims = claims();
+        claims.check()?;
+        assert_eq!(
+            CredentialClaims::from_base64(&*claims.to_base64()?)?,
+            claims
+        );
+
+        let mut wrong_subject = claims.clone();
+        wrong_subject.registered.subject = Some("did:example:other".into());
+        match wrong_subject.check() {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "sub"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+
+        let mut wrong_issuance = claims.clone();
+        wrong_issuance.registered.not_before = Some(1262373805);
+        match wrong_issuance.check() {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "nbf"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+
+        let mut missing_id = claims;
+        missing_id.registered.json_web_token_id = None;
+        match missing_id.check() {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "jti"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn presentation_correspondence() -> Result<(), Error> {
+        let claims = PresentationClaims {
+            registered: RegisteredClaims {
+                issuer: Some("did:example:holder".into()),
+                ..Default::default()
+            },
+            presentation: Presentation {
+                context: vec![json!("https://www.w3.org/2018/credentials/v1")],
+                types: vec!["VerifiablePresentation".into()],
+                holder: Some("did:example:holder".into()),
+                verifiable_credential: vec![json!("eyJhbGciOiJIUzI1NiJ9.e30.sig")],
+                ..Default::default()
+            },
+            nonce: Some("challenge".into()),
+            ..Default::default()
+        };
+        claims.check()?;
+        assert_eq!(
+            claims.presentation.credential_tokens().collect::<Vec<_>>(),
+            ["eyJhbGciOiJIUzI1NiJ9.e30.sig"]
+        );
+
+        let mut other_holder = claims;
+        other_holder.presentation.holder = Some("did:example:other".into());
+        match other_holder.check() {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "iss"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+}
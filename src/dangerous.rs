@@ -0,0 +1,179 @@
+//! Reading tokens without verifying their signature. Nothing read here can be
+//! trusted: anyone can create a token with any header and claims. It is meant
+//! for inspecting tokens, e.g. to find which issuer's keys to verify one with,
+//! and is kept in its own module so that such uses are easy to find.
+//!
+//! The claims of a token parsed with `Token::parse_unverified` can likewise
+//! only be read with [unverified_claims](fn.unverified_claims.html), unless the
+//! `unverified-claims` feature is enabled.
+//! ## Examples
+//! ```
+//! use jwt::dangerous;
+//! use jwt::{Claims, Header};
+//!
+//! let token_str = "eyJhbGciOiJIUzI1NiJ9.eyJpc3MiOiJtaWtreWFuZy5jb20ifQ.forged";
+//!
+//! let header: Header = dangerous::decode_header_without_verification(token_str).unwrap();
+//! let claims: Claims = dangerous::decode_claims_without_verification(token_str).unwrap();
+//! assert_eq!(claims.registered.issuer.as_deref(), Some("mikkyang.com"));
+//! ```
+//!
+//! For debugging endpoints and admin tooling, [inspect](fn.inspect.html)
+//! parses as much of a token as it can and reports why it was rejected,
+//! instead of stopping at the first error.
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::dangerous::{self, Inspection, VerificationOutcome};
+//! use jwt::{Claims, Header};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let token_str = "eyJhbGciOiJIUzI1NiJ9.eyJpc3MiOiJtaWtreWFuZy5jb20ifQ.forged";
+//!
+//! let inspection: Inspection<Header, Claims> = dangerous::inspect(token_str, &key);
+//! assert_eq!(inspection.claims.unwrap().registered.issuer.as_deref(), Some("mikkyang.com"));
+//! assert!(matches!(inspection.outcome, VerificationOutcome::Rejected(_)));
+//! ```
+
+use crate::algorithm::VerifyingAlgorithm;
+use crate::error::Error;
+use crate::header::{Header, JoseHeader};
+use crate::token::verified::{split_components, verify};
+use crate::token::Unverified;
+use crate::{Claims, FromBase64, Token};
+
+/// Decode the header of a token without verifying its signature.
+pub fn decode_header_without_verification<H: FromBase64>(token_str: &str) -> Result<H, Error> {
+    let [header_str, _, _] = split_components(token_str)?;
+    H::from_base64(header_str)
+}
+
+/// Decode the claims of a token without verifying its signature.
+pub fn decode_claims_without_verification<C: FromBase64>(token_str: &str) -> Result<C, Error> {
+    let [_, claims_str, _] = split_components(token_str)?;
+    C::from_base64(claims_str)
+}
+
+/// The claims of a token whose signature has not been verified.
+pub fn unverified_claims<'t, H, C>(token: &'t Token<H, C, Unverified<'_>>) -> &'t C {
+    &token.claims
+}
+
+/// The header and claims of a token whose signature has not been verified.
+pub fn into_unverified_parts<H, C>(token: Token<H, C, Unverified<'_>>) -> (H, C) {
+    (token.header, token.claims)
+}
+
+/// Everything that could be read from a token, and whether its signature was
+/// valid. The header and claims are untrusted unless the outcome is
+/// `Verified`.
+#[derive(Debug)]
+pub struct Inspection<H = Header, C = Claims> {
+    pub header: Result<H, Error>,
+    pub claims: Result<C, Error>,
+    pub outcome: VerificationOutcome,
+}
+
+impl<H, C> Inspection<H, C> {
+    pub fn is_verified(&self) -> bool {
+        matches!(self.outcome, VerificationOutcome::Verified)
+    }
+}
+
+/// The result of checking the signature of an inspected token.
+#[derive(Debug)]
+pub enum VerificationOutcome {
+    Verified,
+    /// The signature was checked and rejected, or the key does not accept the
+    /// algorithm of the header.
+    Rejected(Error),
+    /// The token or its header is malformed, so the signature could not be
+    /// checked. The reason is the error of `header`.
+    Unchecked,
+}
+
+/// Parse the header and claims of a token, and check its signature with
+/// `key`, without stopping at the first error. Claims that do not parse do not
+/// keep the signature from being checked.
+pub fn inspect<H, C>(token_str: &str, key: &impl VerifyingAlgorithm) -> Inspection<H, C>
+where
+    H: FromBase64 + JoseHeader,
+    C: FromBase64,
+{
+    let header =
+        split_components(token_str).and_then(|[header_str, _, _]| H::from_base64(header_str));
+    let claims =
+        split_components(token_str).and_then(|[_, claims_str, _]| C::from_base64(claims_str));
+
+    let outcome = match (split_components(token_str), &header) {
+        (Ok([header_str, claims_str, signature_str]), Ok(parsed)) => {
+            let unverified = Unverified {
+                header_str,
+                claims_str,
+                signature_str,
+            };
+            match verify(parsed, &unverified, key) {
+                Ok(()) => VerificationOutcome::Verified,
+                Err(error) => VerificationOutcome::Rejected(error),
+            }
+        }
+        _ => VerificationOutcome::Unchecked,
+    };
+
+    Inspection {
+        header,
+        claims,
+        outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::{Sha256, Sha384};
+
+    use crate::claims::RegisteredClaims;
+    use crate::dangerous::{inspect, Inspection, VerificationOutcome};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+
+    #[test]
+    pub fn inspection() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        };
+        let token_str = claims.sign_with_key(&key)?;
+
+        let inspection: Inspection<Header, RegisteredClaims> = inspect(&token_str, &key);
+        assert!(inspection.is_verified());
+        assert_eq!(inspection.claims?.subject.as_deref(), Some("someone"));
+
+        let other_key: Hmac<Sha384> = Hmac::new_from_slice(b"secret")?;
+        let inspection: Inspection = inspect(&token_str, &other_key);
+        assert!(matches!(
+            inspection.outcome,
+            VerificationOutcome::Rejected(Error::AlgorithmMismatch(..))
+        ));
+        assert!(inspection.header.is_ok());
+
+        // Claims that are not an object still have their signature checked.
+        let array_claims = ["a", "b"].sign_with_key(&key)?;
+        let inspection: Inspection<Header, RegisteredClaims> = inspect(&array_claims, &key);
+        assert!(inspection.is_verified());
+        assert!(matches!(inspection.claims, Err(Error::Json(_))));
+
+        let inspection: Inspection = inspect("e30", &key);
+        assert!(matches!(inspection.outcome, VerificationOutcome::Unchecked));
+        assert!(matches!(inspection.header, Err(Error::NoClaimsComponent)));
+        assert!(matches!(inspection.claims, Err(Error::NoClaimsComponent)));
+
+        let inspection: Inspection = inspect("bm90IGpzb24.e30.c2ln", &key);
+        assert!(matches!(inspection.outcome, VerificationOutcome::Unchecked));
+        assert!(matches!(inspection.header, Err(Error::Json(_))));
+        assert!(inspection.claims.is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,338 @@
+//! The base64 encoding of token components. JWS requires the URL-safe
+//! alphabet without padding, which every component is encoded with. How
+//! strictly that is enforced when decoding is up to an `Engine`: `UrlSafe`
+//! is used throughout the crate, `Strict` also rejects padding, and
+//! `Tolerant` accepts the output of issuers that get the encoding wrong.
+//! Other encodings can be plugged in by implementing `Engine`.
+//!
+//! With the `simd` feature, encoding and the decoding of `UrlSafe` use
+//! [base64-simd](https://docs.rs/base64-simd), which is faster for large
+//! tokens. The results are the same, and the errors of invalid input are
+//! still reported by the `base64` crate.
+//!
+//! Like serde, the crate lets the last of several members with the same name
+//! win. [RFC 8725](https://tools.ietf.org/html/rfc8725) recommends rejecting
+//! such JSON instead, which `RejectDuplicates` does for the header and claims
+//! of tokens parsed with it.
+//! ## Examples
+//! ```
+//! use jwt::encoding::{Engine, Strict, Tolerant, UrlSafe};
+//!
+//! assert_eq!(UrlSafe.encode(b"{\"a\":1}"), "eyJhIjoxfQ");
+//! assert_eq!(UrlSafe.decode(b"eyI=").unwrap(), b"{\"");
+//! assert!(Strict.decode(b"eyI=").is_err());
+//! assert_eq!(Tolerant.decode(b"ab+/").unwrap(), UrlSafe.decode(b"ab-_").unwrap());
+//! ```
+//! ```
+//! use jwt::encoding::{RejectDuplicates, UrlSafe};
+//! use jwt::{Claims, Error, Header, Token};
+//!
+//! // {"sub":"admin","sub":"someone"}
+//! let token_str = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiJhZG1pbiIsInN1YiI6InNvbWVvbmUifQ.c2ln";
+//! let parsed: Result<Token<Header, Claims, _>, _> =
+//!     Token::parse_unverified_with(token_str, &RejectDuplicates(UrlSafe));
+//! assert!(matches!(parsed, Err(Error::DuplicateField(name)) if name == "sub"));
+//! ```
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+use base64::DecodeError;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+
+use crate::error::Error;
+
+/// The configuration of the underlying `base64` crate, for streaming
+/// encoders and decoders.
+pub(crate) const CONFIG: base64::Config = base64::URL_SAFE_NO_PAD;
+
+/// A base64 encoding. Encoding defaults to the URL-safe alphabet without
+/// padding, as required for tokens, so most engines only decide how
+/// leniently to decode.
+pub trait Engine {
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error>;
+
+    fn encode_into(&self, input: &[u8], output: &mut String) {
+        #[cfg(feature = "simd")]
+        base64_simd::URL_SAFE_NO_PAD.encode_append(input, output);
+        #[cfg(not(feature = "simd"))]
+        base64::encode_config_buf(input, CONFIG, output);
+    }
+
+    fn encode(&self, input: &[u8]) -> String {
+        let mut output = String::new();
+        self.encode_into(input, &mut output);
+        output
+    }
+}
+
+impl<E: Engine + ?Sized> Engine for &E {
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        (**self).decode(input)
+    }
+
+    fn encode_into(&self, input: &[u8], output: &mut String) {
+        (**self).encode_into(input, output)
+    }
+}
+
+/// The URL-safe alphabet. Padding is tolerated when decoding, as long as it
+/// is correct.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UrlSafe;
+
+impl Engine for UrlSafe {
+    #[cfg(feature = "simd")]
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let simd = if input.last() == Some(&b'=') {
+            base64_simd::URL_SAFE
+        } else {
+            base64_simd::URL_SAFE_NO_PAD
+        };
+        match simd.decode_to_vec(input) {
+            Ok(output) => Ok(output),
+            // base64-simd does not say what is wrong with the input.
+            Err(_) => Ok(base64::decode_config(input, CONFIG)?),
+        }
+    }
+
+    #[cfg(not(feature = "simd"))]
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(base64::decode_config(input, CONFIG)?)
+    }
+}
+
+/// The URL-safe alphabet, rejecting any padding when decoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Strict;
+
+impl Engine for Strict {
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        if let Some(offset) = input.iter().position(|b| *b == b'=') {
+            return Err(Error::Base64(DecodeError::InvalidByte(offset, b'=')));
+        }
+        UrlSafe.decode(input)
+    }
+}
+
+/// Either alphabet, with or without padding. For tokens from issuers that use
+/// the standard alphabet or pad their components.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tolerant;
+
+impl Engine for Tolerant {
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let end = input
+            .iter()
+            .rposition(|b| *b != b'=')
+            .map_or(0, |last| last + 1);
+        let normalized: Vec<u8> = input[..end]
+            .iter()
+            .map(|b| match b {
+                b'+' => b'-',
+                b'/' => b'_',
+                b => *b,
+            })
+            .collect();
+        UrlSafe.decode(&normalized)
+    }
+}
+
+/// Decodes with another engine, then rejects JSON in which an object has
+/// several members with the same name with `Error::DuplicateField`. Only for
+/// JSON components, such as the header and claims.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RejectDuplicates<E = UrlSafe>(pub E);
+
+impl<E: Engine> Engine for RejectDuplicates<E> {
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
+        let json = self.0.decode(input)?;
+        check_duplicates(&json)?;
+        Ok(json)
+    }
+
+    fn encode_into(&self, input: &[u8], output: &mut String) {
+        self.0.encode_into(input, output)
+    }
+}
+
+/// Check that no object in `json` has several members with the same name.
+/// Names are compared after unescaping, so `"a"` and `"\u0061"` are the same.
+pub fn check_duplicates(json: &[u8]) -> Result<(), Error> {
+    let duplicate = RefCell::new(None);
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    UniqueKeys(&duplicate)
+        .deserialize(&mut deserializer)
+        .and_then(|_| deserializer.end())
+        .map_err(|e| match duplicate.into_inner() {
+            Some(name) => Error::DuplicateField(name),
+            None => Error::Json(e),
+        })
+}
+
+/// Walks a JSON value, recording the first duplicated member name.
+struct UniqueKeys<'a>(&'a RefCell<Option<String>>);
+
+impl<'de> DeserializeSeed<'de> for UniqueKeys<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for UniqueKeys<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<Er>(self, _: bool) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_i64<Er>(self, _: i64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_u64<Er>(self, _: u64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_f64<Er>(self, _: f64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_str<Er>(self, _: &str) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_unit<Er>(self) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while seq.next_element_seed(UniqueKeys(self.0))?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut names = BTreeSet::new();
+        while let Some(name) = map.next_key::<String>()? {
+            if names.contains(&name) {
+                let message = alloc::format!("duplicate field `{}`", name);
+                *self.0.borrow_mut() = Some(name);
+                return Err(de::Error::custom(message));
+            }
+            names.insert(name);
+            map.next_value_seed(UniqueKeys(self.0))?;
+        }
+        Ok(())
+    }
+}
+
+/// Encode with the crate's default engine.
+pub(crate) fn encode(input: impl AsRef<[u8]>) -> String {
+    UrlSafe.encode(input.as_ref())
+}
+
+/// Encode with the crate's default engine, appending to `output`.
+pub(crate) fn encode_into(input: impl AsRef<[u8]>, output: &mut String) {
+    UrlSafe.encode_into(input.as_ref(), output)
+}
+
+/// The length of the unpadded base64 encoding of `len` bytes.
+pub(crate) fn encoded_len(len: usize) -> usize {
+    (len * 4).div_ceil(3)
+}
+
+/// Decode with the crate's default engine.
+pub(crate) fn decode(input: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    UrlSafe.decode(input.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{check_duplicates, Engine, RejectDuplicates, Strict, Tolerant, UrlSafe};
+    use crate::error::Error;
+
+    #[test]
+    pub fn engines() -> Result<(), Error> {
+        let bytes = [0xfb, 0xff, 0xbf];
+        assert_eq!(UrlSafe.encode(&bytes), "-_-_");
+        assert_eq!(Strict.encode(&bytes), "-_-_");
+        assert_eq!(Tolerant.encode(&bytes), "-_-_");
+
+        for engine in [&UrlSafe as &dyn Engine, &Strict, &Tolerant] {
+            assert_eq!(engine.decode(b"-_-_")?, bytes);
+            assert_eq!(engine.decode(b"eyI")?, b"{\"");
+        }
+
+        assert_eq!(UrlSafe.decode(b"eyI=")?, b"{\"");
+        assert!(matches!(Strict.decode(b"eyI="), Err(Error::Base64(_))));
+        assert_eq!(Tolerant.decode(b"eyI=")?, b"{\"");
+
+        assert!(UrlSafe.decode(b"+/+/").is_err());
+        assert!(Strict.decode(b"+/+/").is_err());
+        assert_eq!(Tolerant.decode(b"+/+/")?, bytes);
+        Ok(())
+    }
+
+    #[test]
+    pub fn same_as_base64_crate() {
+        use crate::encoding::CONFIG;
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..70 {
+            let input = &bytes[len..len * 2];
+            let encoded = UrlSafe.encode(input);
+            assert_eq!(encoded, base64::encode_config(input, CONFIG));
+            assert_eq!(UrlSafe.decode(encoded.as_bytes()).unwrap(), input);
+        }
+
+        for input in [
+            "eyI=",
+            "eyI==",
+            "eyJ",
+            "eyI",
+            "e",
+            "ey=I",
+            "eyI*",
+            "eyJhbGciOiJub25lIn0=",
+        ] {
+            let expected = base64::decode_config(input, CONFIG).ok();
+            assert_eq!(UrlSafe.decode(input.as_bytes()).ok(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    pub fn duplicates() -> Result<(), Error> {
+        check_duplicates(br#"{"a":1,"b":{"a":2},"c":[{"a":3},{"a":4}]}"#)?;
+
+        for (json, name) in [
+            (r#"{"sub":"a","sub":"b"}"#, "sub"),
+            (r#"{"a":1,"\u0061":2}"#, "a"),
+            (r#"{"a":{"b":[{"c":1,"c":1}]}}"#, "c"),
+        ] {
+            match check_duplicates(json.as_bytes()) {
+                Err(Error::DuplicateField(duplicate)) => assert_eq!(duplicate, name),
+                other => panic!("Duplicate in {} should be rejected: {:?}", json, other),
+            }
+        }
+        assert!(matches!(check_duplicates(b"{\"a\":"), Err(Error::Json(_))));
+
+        let encoded = UrlSafe.encode(br#"{"a":1,"a":2}"#);
+        let last_wins: serde_json::Value =
+            serde_json::from_slice(&UrlSafe.decode(encoded.as_bytes())?)?;
+        assert_eq!(last_wins["a"], 2);
+        assert!(matches!(
+            RejectDuplicates(Tolerant).decode(encoded.as_bytes()),
+            Err(Error::DuplicateField(_))
+        ));
+        Ok(())
+    }
+}
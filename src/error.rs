@@ -1,30 +1,72 @@
-use std::fmt;
-use std::string::FromUtf8Error;
+use alloc::string::{FromUtf8Error, String, ToString};
+use core::fmt;
 
 use base64::DecodeError;
 use crypto_common::InvalidLength;
 use digest::MacError;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde_json::Error as JsonError;
 
 use self::Error::*;
 use crate::algorithm::AlgorithmType;
 
+/// The errors of every operation of the crate. The `Display` messages are
+/// meant for people and may change between releases. For matching errors
+/// outside of Rust, e.g. in the body of an HTTP response, use
+/// [code](#method.code), which is stable. Errors serialize as an object with
+/// the `code` and `message`.
+/// ## Examples
+/// ```
+/// use jwt::Error;
+///
+/// assert_eq!(Error::Expired.code(), "ERR_JWT_EXPIRED");
+/// assert_eq!(
+///     serde_json::to_string(&Error::Expired).unwrap(),
+///     r#"{"code":"ERR_JWT_EXPIRED","message":"Token has expired"}"#
+/// );
+/// ```
 #[derive(Debug)]
 pub enum Error {
     AlgorithmMismatch(AlgorithmType, AlgorithmType),
     Base64(DecodeError),
+    DuplicateField(String),
+    EmptyKeyId,
+    Expired,
     Format,
+    InvalidClaim(String),
     InvalidSignature,
+    InvalidTimestamps,
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
     Json(JsonError),
+    /// A JSON error at a known place: in the `component` of a token, `header`
+    /// or `claims`, and with the `json-path` feature, at the `path` of the
+    /// member that failed to deserialize. `source` is the original error,
+    /// which still has its line, column and category.
+    JsonAt {
+        component: Option<&'static str>,
+        path: Option<String>,
+        source: JsonError,
+    },
+    MissingClaim(String),
     NoClaimsComponent,
     NoHeaderComponent,
     NoKeyId,
     NoKeyWithKeyId(String),
     NoSignatureComponent,
+    NotYetValid,
+    #[cfg(feature = "random")]
+    Random(getrandom::Error),
     RustCryptoMac(MacError),
     RustCryptoMacKeyLength(InvalidLength),
+    Throttled,
+    TokenTooLarge(usize),
     TooManyComponents,
+    UnknownIssuer(String),
+    UnpinnedKey(String),
+    UnsupportedAlgorithm(AlgorithmType),
     Utf8(FromUtf8Error),
+    WeakKey(AlgorithmType),
     #[cfg(feature = "openssl")]
     OpenSsl(openssl::error::ErrorStack),
 }
@@ -35,17 +77,50 @@ impl fmt::Display for Error {
             AlgorithmMismatch(a, b) => {
                 write!(f, "Expected algorithm type {:?} but found {:?}", a, b)
             }
+            UnsupportedAlgorithm(a) => write!(f, "Algorithm type {:?} is not supported", a),
             NoKeyId => write!(f, "No key id found"),
+            EmptyKeyId => write!(f, "Key id is empty"),
             NoKeyWithKeyId(ref kid) => write!(f, "Key with key id {} not found", kid),
             NoHeaderComponent => write!(f, "No header component found in token string"),
             NoClaimsComponent => write!(f, "No claims component found in token string"),
             NoSignatureComponent => write!(f, "No signature component found in token string"),
             TooManyComponents => write!(f, "Too many components found in token string"),
+            TokenTooLarge(max) => write!(f, "Token is longer than {} bytes", max),
             Format => write!(f, "Format"),
             InvalidSignature => write!(f, "Invalid signature"),
+            InvalidTimestamps => write!(f, "Token has impossible timestamps"),
+            InvalidClaim(ref name) => write!(f, "Claim {} has an invalid value", name),
+            MissingClaim(ref name) => write!(f, "Claim {} is missing", name),
+            DuplicateField(ref name) => write!(f, "Field {} occurs more than once", name),
+            Expired => write!(f, "Token has expired"),
+            NotYetValid => write!(f, "Token is not valid yet"),
+            UnknownIssuer(ref issuer) => write!(f, "Issuer {} is not trusted", issuer),
+            UnpinnedKey(ref thumbprint) => {
+                write!(f, "Key with thumbprint {} is not pinned", thumbprint)
+            }
+            WeakKey(a) => write!(f, "Key is too weak for algorithm type {:?}", a),
+            Throttled => write!(f, "Too many failed verifications"),
             Base64(ref x) => write!(f, "{}", x),
             Json(ref x) => write!(f, "{}", x),
+            JsonAt {
+                component,
+                ref path,
+                ref source,
+            } => {
+                let path = path.as_deref().unwrap_or("");
+                match component {
+                    Some(component) if path.is_empty() || path.starts_with('[') => {
+                        write!(f, "{}{}: {}", component, path, source)
+                    }
+                    Some(component) => write!(f, "{}.{}: {}", component, path, source),
+                    None => write!(f, "{}: {}", path, source),
+                }
+            }
             Utf8(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "std")]
+            Io(ref x) => write!(f, "{}", x),
+            #[cfg(feature = "random")]
+            Random(ref x) => write!(f, "{}", x),
             RustCryptoMac(ref x) => write!(f, "{}", x),
             RustCryptoMacKeyLength(ref x) => write!(f, "{}", x),
             #[cfg(feature = "openssl")]
@@ -54,8 +129,82 @@ impl fmt::Display for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+impl Error {
+    /// A code identifying the kind of error, without any of its details.
+    /// Codes are not changed once released. Errors of the underlying
+    /// libraries only have the code of their library.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            AlgorithmMismatch(..) => "ERR_JWT_ALGORITHM_MISMATCH",
+            Base64(_) => "ERR_JWT_BASE64",
+            DuplicateField(_) => "ERR_JWT_DUPLICATE_FIELD",
+            EmptyKeyId => "ERR_JWT_EMPTY_KEY_ID",
+            Expired => "ERR_JWT_EXPIRED",
+            Format => "ERR_JWT_FORMAT",
+            InvalidClaim(_) => "ERR_JWT_INVALID_CLAIM",
+            InvalidSignature | RustCryptoMac(_) => "ERR_JWT_INVALID_SIGNATURE",
+            InvalidTimestamps => "ERR_JWT_INVALID_TIMESTAMPS",
+            #[cfg(feature = "std")]
+            Io(_) => "ERR_JWT_IO",
+            Json(_) | JsonAt { .. } => "ERR_JWT_JSON",
+            MissingClaim(_) => "ERR_JWT_MISSING_CLAIM",
+            NoClaimsComponent => "ERR_JWT_NO_CLAIMS_COMPONENT",
+            NoHeaderComponent => "ERR_JWT_NO_HEADER_COMPONENT",
+            NoKeyId => "ERR_JWT_NO_KEY_ID",
+            NoKeyWithKeyId(_) => "ERR_JWT_UNKNOWN_KEY_ID",
+            NoSignatureComponent => "ERR_JWT_NO_SIGNATURE_COMPONENT",
+            NotYetValid => "ERR_JWT_NOT_YET_VALID",
+            #[cfg(feature = "random")]
+            Random(_) => "ERR_JWT_RANDOM",
+            RustCryptoMacKeyLength(_) => "ERR_JWT_KEY_LENGTH",
+            Throttled => "ERR_JWT_THROTTLED",
+            TokenTooLarge(_) => "ERR_JWT_TOKEN_TOO_LARGE",
+            TooManyComponents => "ERR_JWT_TOO_MANY_COMPONENTS",
+            UnknownIssuer(_) => "ERR_JWT_UNKNOWN_ISSUER",
+            UnpinnedKey(_) => "ERR_JWT_UNPINNED_KEY",
+            UnsupportedAlgorithm(_) => "ERR_JWT_UNSUPPORTED_ALGORITHM",
+            Utf8(_) => "ERR_JWT_UTF8",
+            WeakKey(_) => "ERR_JWT_WEAK_KEY",
+            #[cfg(feature = "openssl")]
+            OpenSsl(_) => "ERR_JWT_OPENSSL",
+        }
+    }
+}
+
+/// Record the token component, e.g. `header` or `claims`, that a JSON error
+/// occurred in.
+pub(crate) fn in_component(error: Error, component: &'static str) -> Error {
+    match error {
+        Json(source) => JsonAt {
+            component: Some(component),
+            path: None,
+            source,
+        },
+        JsonAt {
+            component: None,
+            path,
+            source,
+        } => JsonAt {
+            component: Some(component),
+            path,
+            source,
+        },
+        error => error,
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("Error", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
 macro_rules! error_wrap {
     ($f:ty, $e:expr) => {
         impl From<$f> for Error {
@@ -69,7 +218,36 @@ macro_rules! error_wrap {
 error_wrap!(DecodeError, Base64);
 error_wrap!(JsonError, Json);
 error_wrap!(FromUtf8Error, Utf8);
+#[cfg(feature = "std")]
+error_wrap!(std::io::Error, Io);
+#[cfg(feature = "random")]
+error_wrap!(getrandom::Error, Random);
 error_wrap!(MacError, RustCryptoMac);
 error_wrap!(InvalidLength, RustCryptoMacKeyLength);
 #[cfg(feature = "openssl")]
 error_wrap!(openssl::error::ErrorStack, Error::OpenSsl);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::algorithm::AlgorithmType;
+    use crate::error::Error;
+
+    #[test]
+    pub fn codes() {
+        assert_eq!(Error::InvalidSignature.code(), "ERR_JWT_INVALID_SIGNATURE");
+        assert_eq!(
+            Error::RustCryptoMac(digest::MacError).code(),
+            Error::InvalidSignature.code()
+        );
+        assert_eq!(
+            serde_json::to_value(Error::MissingClaim("exp".into())).unwrap(),
+            json!({"code": "ERR_JWT_MISSING_CLAIM", "message": "Claim exp is missing"})
+        );
+        assert_eq!(
+            serde_json::to_value(Error::UnsupportedAlgorithm(AlgorithmType::None)).unwrap()["code"],
+            "ERR_JWT_UNSUPPORTED_ALGORITHM"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Metrics of signing, verification, and validation, recorded through the
+//! `metrics` facade when the `metrics` feature is enabled, and compiled away
+//! otherwise. The metrics are listed in the README. Verifications are also
+//! passed on to the [audit](../audit/index.html) sink with the `std` feature.
+
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::Unverified;
+
+/// Measures the duration of an operation.
+pub(crate) struct Timer {
+    #[cfg(feature = "metrics")]
+    started: std::time::Instant,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Timer {
+            #[cfg(feature = "metrics")]
+            started: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn signed<T>(algorithm: AlgorithmType, timer: Timer, result: &Result<T, Error>) {
+    record("jwt_sign", algorithm, timer, result);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn signed<T>(_: AlgorithmType, _: Timer, _: &Result<T, Error>) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn verified<T>(algorithm: AlgorithmType, timer: Timer, result: &Result<T, Error>) {
+    record("jwt_verify", algorithm, timer, result);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn verified<T>(_: AlgorithmType, _: Timer, _: &Result<T, Error>) {}
+
+#[cfg(feature = "std")]
+pub(crate) fn audited(
+    header: &impl JoseHeader,
+    unverified: &Unverified,
+    result: &Result<(), Error>,
+) {
+    crate::audit::verified(header, unverified, result);
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn audited(_: &impl JoseHeader, _: &Unverified, _: &Result<(), Error>) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn store_miss(operation: &'static str) {
+    metrics::counter!("jwt_store_misses_total", "operation" => operation).increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn store_miss(_: &'static str) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn validation_failed(error: &Error) {
+    metrics::counter!("jwt_validation_failures_total", "error" => error_kind(error)).increment(1);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn validation_failed(_: &Error) {}
+
+#[cfg(feature = "metrics")]
+fn record<T>(prefix: &str, algorithm: AlgorithmType, timer: Timer, result: &Result<T, Error>) {
+    let algorithm = algorithm_name(algorithm);
+    let outcome = match result {
+        Ok(_) => "ok",
+        Err(error) => error_kind(error),
+    };
+    metrics::counter!(format!("{}_total", prefix), "algorithm" => algorithm, "result" => outcome)
+        .increment(1);
+    metrics::histogram!(format!("{}_duration_seconds", prefix), "algorithm" => algorithm)
+        .record(timer.started.elapsed());
+}
+
+#[cfg(feature = "metrics")]
+fn algorithm_name(algorithm: AlgorithmType) -> &'static str {
+    match algorithm {
+        AlgorithmType::Hs256 => "HS256",
+        AlgorithmType::Hs384 => "HS384",
+        AlgorithmType::Hs512 => "HS512",
+        AlgorithmType::Rs256 => "RS256",
+        AlgorithmType::Rs384 => "RS384",
+        AlgorithmType::Rs512 => "RS512",
+        AlgorithmType::Es256 => "ES256",
+        AlgorithmType::Es384 => "ES384",
+        AlgorithmType::Es512 => "ES512",
+        AlgorithmType::Ps256 => "PS256",
+        AlgorithmType::Ps384 => "PS384",
+        AlgorithmType::Ps512 => "PS512",
+        AlgorithmType::None => "none",
+    }
+}
+
+/// A label for the kind of an error, without any of its details.
+#[cfg(feature = "std")]
+pub(crate) fn error_kind(error: &Error) -> &'static str {
+    match error {
+        Error::AlgorithmMismatch(..) => "algorithm_mismatch",
+        Error::Base64(_) => "base64",
+        Error::DuplicateField(_) => "duplicate_field",
+        Error::EmptyKeyId => "empty_key_id",
+        Error::Expired => "expired",
+        Error::Format => "format",
+        Error::InvalidClaim(_) => "invalid_claim",
+        Error::InvalidSignature | Error::RustCryptoMac(_) => "invalid_signature",
+        Error::InvalidTimestamps => "invalid_timestamps",
+        Error::Io(_) => "io",
+        Error::Json(_) | Error::JsonAt { .. } => "json",
+        Error::MissingClaim(_) => "missing_claim",
+        Error::NoClaimsComponent
+        | Error::NoHeaderComponent
+        | Error::NoSignatureComponent
+        | Error::TooManyComponents => "malformed",
+        Error::NoKeyId => "no_key_id",
+        Error::NoKeyWithKeyId(_) => "unknown_key_id",
+        Error::NotYetValid => "not_yet_valid",
+        #[cfg(feature = "random")]
+        Error::Random(_) => "random",
+        Error::RustCryptoMacKeyLength(_) => "key_length",
+        Error::Throttled => "throttled",
+        Error::TokenTooLarge(_) => "too_large",
+        Error::UnknownIssuer(_) => "unknown_issuer",
+        Error::UnpinnedKey(_) => "unpinned_key",
+        Error::UnsupportedAlgorithm(_) => "unsupported_algorithm",
+        Error::Utf8(_) => "utf8",
+        Error::WeakKey(_) => "weak_key",
+        #[cfg(feature = "openssl")]
+        Error::OpenSsl(_) => "openssl",
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+    use sha2::Sha256;
+    use std::sync::{Arc, Mutex};
+
+    use crate::claims::Claims;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::validation::Validation;
+    use crate::Token;
+
+    #[derive(Clone, Default)]
+    struct Keys(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder for Keys {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            self.0.lock().unwrap().push(key.to_string());
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            self.0.lock().unwrap().push(key.to_string());
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    pub fn records() -> Result<(), Error> {
+        let keys = Keys::default();
+        metrics::with_local_recorder(&keys, || -> Result<(), Error> {
+            let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+            let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+            let token_str = Claims::default().sign_with_key(&key)?;
+            let result: Result<Claims, Error> = token_str.as_str().verify_with_key(&wrong_key);
+            assert!(result.is_err());
+
+            let token: Token<Header, Claims, _> = token_str.as_str().verify_with_key(&key)?;
+            assert!(token
+                .validate_with(&Validation::new().require("sub"))
+                .is_err());
+            Ok(())
+        })?;
+
+        let keys = keys.0.lock().unwrap();
+        for expected in [
+            "Key(jwt_sign_total, [algorithm = HS256, result = ok])",
+            "Key(jwt_sign_duration_seconds, [algorithm = HS256])",
+            "Key(jwt_verify_total, [algorithm = HS256, result = invalid_signature])",
+            "Key(jwt_validation_failures_total, [error = missing_claim])",
+        ] {
+            assert!(
+                keys.iter().any(|key| key == expected),
+                "{} in {:?}",
+                expected,
+                keys
+            );
+        }
+        Ok(())
+    }
+}
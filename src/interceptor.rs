@@ -0,0 +1,204 @@
+//! A [tonic](https://docs.rs/tonic) `Interceptor` that verifies the bearer
+//! token of every gRPC request, with the `tonic` feature. The token is read
+//! from the `authorization` metadata, verified with a key or a store and
+//! validated against the platform clock. The claims of an accepted token are
+//! added to the extensions of the request, where the service can read them,
+//! and any other request is refused as `Unauthenticated`.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::interceptor::TokenInterceptor;
+//! use jwt::validation::Validation;
+//! use jwt::{Claims, RegisteredClaims, SignWithKey};
+//! use sha2::Sha256;
+//! use tonic::service::Interceptor;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let token_str = Claims::new(RegisteredClaims {
+//!     subject: Some("someone".into()),
+//!     ..Default::default()
+//! })
+//! .sign_with_key(&key)
+//! .unwrap();
+//!
+//! let mut interceptor = TokenInterceptor::<Claims>::with_key(key, Validation::new());
+//! let mut request = tonic::Request::new(());
+//! let authorization = format!("Bearer {}", token_str).parse().unwrap();
+//! request.metadata_mut().insert("authorization", authorization);
+//!
+//! let request = interceptor.call(request).unwrap();
+//! let claims = request.extensions().get::<Claims>().unwrap();
+//! assert_eq!(claims.registered.subject.as_deref(), Some("someone"));
+//! ```
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::marker::PhantomData;
+
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::algorithm::store::Store;
+use crate::algorithm::VerifyingAlgorithm;
+use crate::claims::RegisteredClaims;
+use crate::error::Error;
+use crate::header::{Header, JoseHeader};
+use crate::token::verified::VerifyWithStore;
+use crate::token::{Unverified, Validated, Verified};
+use crate::validation::{verify_and_validate_with_key, Validation};
+use crate::{FromBase64, Token};
+
+type Verify<C> = dyn Fn(&str) -> Result<C, Error> + Send + Sync;
+
+/// Verifies the bearer token of each request, adding its claims `C` to the
+/// request's extensions. Clones share the same key or store.
+pub struct TokenInterceptor<C, H = Header> {
+    verify: Arc<Verify<C>>,
+    header: PhantomData<fn() -> H>,
+}
+
+impl<C, H> TokenInterceptor<C, H>
+where
+    C: FromBase64 + AsRef<RegisteredClaims> + Clone + Send + Sync + 'static,
+    H: FromBase64 + JoseHeader + 'static,
+{
+    /// Verify tokens with `key`, and check them with `validation`.
+    pub fn with_key<K>(key: K, validation: Validation) -> Self
+    where
+        K: VerifyingAlgorithm + Send + Sync + 'static,
+    {
+        Self::new(move |token_str| {
+            let token: Token<H, C, Validated> =
+                verify_and_validate_with_key(token_str, &key, &validation)?;
+            let (_, claims) = token.into();
+            Ok(claims)
+        })
+    }
+
+    /// Verify tokens with the key of `store` named by their `kid`, and check
+    /// them with `validation`.
+    pub fn with_store<S, A>(store: S, validation: Validation) -> Self
+    where
+        S: Store<Algorithm = A> + Send + Sync + 'static,
+        A: VerifyingAlgorithm,
+    {
+        Self::new(move |token_str| {
+            validation.check_token_str(token_str)?;
+            let unverified: Token<H, C, Unverified> = Token::parse_unverified(token_str)?;
+            let token: Token<H, C, Verified> = unverified.verify_with_store(&store)?;
+            validation.validate_token(token.header(), token.claims())?;
+            let (_, claims) = token.into();
+            Ok(claims)
+        })
+    }
+
+    fn new(verify: impl Fn(&str) -> Result<C, Error> + Send + Sync + 'static) -> Self {
+        TokenInterceptor {
+            verify: Arc::new(verify),
+            header: PhantomData,
+        }
+    }
+}
+
+impl<C, H> Clone for TokenInterceptor<C, H> {
+    fn clone(&self) -> Self {
+        TokenInterceptor {
+            verify: Arc::clone(&self.verify),
+            header: PhantomData,
+        }
+    }
+}
+
+impl<C, H> fmt::Debug for TokenInterceptor<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TokenInterceptor").finish_non_exhaustive()
+    }
+}
+
+impl<C, H> Interceptor for TokenInterceptor<C, H>
+where
+    C: Clone + Send + Sync + 'static,
+{
+    /// Refuse the request as `Unauthenticated` if it has no bearer token, or
+    /// with the code of the error if the token is rejected.
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token_str = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(crate::extract::bearer)
+            .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+        let claims = (self.verify)(token_str).map_err(|e| Status::unauthenticated(e.code()))?;
+        request.extensions_mut().insert(claims);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use tonic::service::Interceptor;
+    use tonic::{Code, Request};
+
+    use crate::algorithm::store::InMemoryStore;
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::interceptor::TokenInterceptor;
+    use crate::token::signed::SignWithKey;
+    use crate::validation::Validation;
+    use crate::Token;
+
+    fn request(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            let value = authorization.parse().unwrap();
+            request.metadata_mut().insert("authorization", value);
+        }
+        request
+    }
+
+    #[test]
+    pub fn intercept() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = Claims::new(RegisteredClaims {
+            subject: Some("someone".into()),
+            expiration: Some(1),
+            ..Default::default()
+        });
+        let header = Header {
+            key_id: Some("main".into()),
+            ..Default::default()
+        };
+        let expired = Token::new(header.clone(), claims.clone()).sign_with_key(&key)?;
+        let mut claims = claims;
+        claims.registered.expiration = None;
+        let token = Token::new(header, claims).sign_with_key(&key)?;
+
+        let mut store = InMemoryStore::new();
+        store.insert("main", key.clone());
+        let interceptors = [
+            TokenInterceptor::<Claims>::with_key(key, Validation::new()),
+            TokenInterceptor::<Claims>::with_store(store, Validation::new()),
+        ];
+        for interceptor in interceptors.iter() {
+            let mut interceptor = interceptor.clone();
+            let accepted = interceptor.call(request(Some(&token.to_bearer()))).unwrap();
+            let claims = accepted.extensions().get::<Claims>().unwrap();
+            assert_eq!(claims.registered.subject.as_deref(), Some("someone"));
+
+            for authorization in [None, Some("Basic dXNlcjpwYXNz")] {
+                let status = interceptor.call(request(authorization)).unwrap_err();
+                assert_eq!(status.code(), Code::Unauthenticated);
+            }
+
+            let status = interceptor
+                .call(request(Some(&expired.to_bearer())))
+                .unwrap_err();
+            assert_eq!(status.code(), Code::Unauthenticated);
+            assert_eq!(status.message(), "ERR_JWT_EXPIRED");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+//! JSON Web Key Sets, as defined in
+//! [RFC 7517](https://tools.ietf.org/html/rfc7517#section-5). A set fetched
+//! from an issuer can be kept as a `JwkSnapshot`, recording when it was
+//! fetched, and saved to disk to be loaded at the next startup, e.g. by
+//! deployments that cannot reach the issuer. Pinning the thumbprints of the
+//! expected keys protects against a compromised JWKS endpoint or snapshot
+//! file.
+//! ## Examples
+//! ```
+//! use jwt::jwks::{JwkSet, JwkSnapshot};
+//!
+//! let jwks: JwkSet = serde_json::from_str(r#"{"keys": [{
+//!     "kty": "EC",
+//!     "crv": "P-256",
+//!     "kid": "first",
+//!     "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+//!     "y": "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0"
+//! }]}"#)
+//! .unwrap();
+//! jwks.check_pins(&["oKIywvGUpTVTyxMQ3bwIIeQUudfr_CkLMjCE19ECD-U"]).unwrap();
+//! assert!(jwks.check_pins(&["another-thumbprint"]).is_err());
+//!
+//! let path = std::env::temp_dir().join("jwt-example-jwks.json");
+//! JwkSnapshot::new(jwks, 1302319100).save(&path).unwrap();
+//!
+//! let snapshot = JwkSnapshot::load(&path).unwrap();
+//! assert_eq!(snapshot.age(1302322700), 3600);
+//! assert!(snapshot.jwks.get("first").is_some());
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::algorithm::store::PinnedStore;
+#[cfg(feature = "openssl")]
+use crate::algorithm::verifier_from_jwk;
+use crate::algorithm::{hmac_from_jwk, AlgorithmType, VerifyingAlgorithm};
+use crate::claims::SecondsSinceEpoch;
+use crate::confirmation::jwk_thumbprint;
+use crate::error::Error;
+
+/// A set of JWKs, each kept as the JSON object it was given as.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Map<String, Value>>,
+}
+
+impl JwkSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The key whose `kid` is `key_id`.
+    pub fn get(&self, key_id: &str) -> Option<&Map<String, Value>> {
+        self.keys
+            .iter()
+            .find(|jwk| jwk.get("kid").and_then(Value::as_str) == Some(key_id))
+    }
+
+    /// The [RFC 7638](https://tools.ietf.org/html/rfc7638) thumbprints of the
+    /// keys, in order.
+    pub fn thumbprints(&self) -> Result<Vec<String>, Error> {
+        self.keys.iter().map(jwk_thumbprint).collect()
+    }
+
+    /// Fail with `Error::UnpinnedKey`, naming the thumbprint of the first key
+    /// whose thumbprint is not one of `pins`. When keys are rotated, the
+    /// thumbprint of the new key must be pinned before it is published.
+    pub fn check_pins<P: AsRef<str>>(&self, pins: &[P]) -> Result<(), Error> {
+        for thumbprint in self.thumbprints()? {
+            if !pins.iter().any(|pin| pin.as_ref() == thumbprint) {
+                return Err(Error::UnpinnedKey(thumbprint));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load the keys of the set for verifying tokens, each stored under its
+    /// `kid` and pinned to the algorithm of its `alg`. Keys without either,
+    /// keys for encryption, and keys of algorithms that are not supported are
+    /// left out. `oct` keys are loaded as HMAC keys, and RSA and EC keys
+    /// require the `openssl` feature. The thumbprints of the keys are not
+    /// checked; use [verifying_store_with_pins](#method.verifying_store_with_pins)
+    /// for a set whose keys are pinned.
+    pub fn verifying_store(
+        &self,
+    ) -> Result<PinnedStore<Box<dyn VerifyingAlgorithm + Send + Sync>>, Error> {
+        let mut store = PinnedStore::new();
+        for jwk in &self.keys {
+            if jwk.get("use").and_then(Value::as_str) == Some("enc") {
+                continue;
+            }
+            let key_id = match jwk.get("kid").and_then(Value::as_str) {
+                Some(key_id) => key_id,
+                None => continue,
+            };
+            let algorithm = match jwk.get("alg").cloned().map(serde_json::from_value) {
+                Some(Ok(algorithm)) => algorithm,
+                _ => continue,
+            };
+
+            match verifier(jwk, algorithm) {
+                Ok(key) => {
+                    store.insert(key_id, algorithm, key);
+                }
+                Err(Error::UnsupportedAlgorithm(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(store)
+    }
+
+    /// Load the keys like [verifying_store](#method.verifying_store), after
+    /// checking them with [check_pins](#method.check_pins). No keys are
+    /// loaded if any key of the set is not pinned, including keys that would
+    /// be left out of the store.
+    pub fn verifying_store_with_pins<P: AsRef<str>>(
+        &self,
+        pins: &[P],
+    ) -> Result<PinnedStore<Box<dyn VerifyingAlgorithm + Send + Sync>>, Error> {
+        self.check_pins(pins)?;
+        self.verifying_store()
+    }
+}
+
+fn verifier(
+    jwk: &Map<String, Value>,
+    algorithm: AlgorithmType,
+) -> Result<Box<dyn VerifyingAlgorithm + Send + Sync>, Error> {
+    match jwk.get("kty").and_then(Value::as_str) {
+        Some("oct") => Ok(Box::new(hmac_from_jwk(jwk, algorithm)?)),
+        #[cfg(feature = "openssl")]
+        _ => verifier_from_jwk(jwk, algorithm),
+        #[cfg(not(feature = "openssl"))]
+        _ => Err(Error::UnsupportedAlgorithm(algorithm)),
+    }
+}
+
+/// A JWK set and the time it was fetched at. It serializes as the set with
+/// an additional `fetched_at` member, so a saved snapshot is still a valid
+/// JWK set document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JwkSnapshot {
+    pub fetched_at: SecondsSinceEpoch,
+    #[serde(flatten)]
+    pub jwks: JwkSet,
+}
+
+impl JwkSnapshot {
+    pub fn new(jwks: JwkSet, fetched_at: SecondsSinceEpoch) -> Self {
+        JwkSnapshot { fetched_at, jwks }
+    }
+
+    /// The seconds elapsed since the set was fetched, at `now`.
+    pub fn age(&self, now: SecondsSinceEpoch) -> SecondsSinceEpoch {
+        now.saturating_sub(self.fetched_at)
+    }
+
+    /// Write the snapshot to `path` as JSON. It is written to a temporary
+    /// file next to `path` first, so that a crash while saving leaves the
+    /// previous snapshot in place.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        std::fs::write(&temporary, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Read a snapshot written by [save](#method.save).
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::error::Error;
+    use crate::jwks::{JwkSet, JwkSnapshot};
+
+    fn jwks() -> JwkSet {
+        serde_json::from_value(json!({"keys": [
+            {
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "first",
+                "x": "f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU",
+                "y": "x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0",
+            },
+            {"kty": "oct", "kid": "second", "k": "YS1zZWNyZXQtd2hpY2gtaXMtMzItYnl0ZXMtbG9uZyE"},
+        ]}))
+        .unwrap()
+    }
+
+    #[test]
+    pub fn pins() -> Result<(), Error> {
+        let jwks = jwks();
+        let thumbprints = jwks.thumbprints()?;
+        assert_eq!(
+            thumbprints[0],
+            "oKIywvGUpTVTyxMQ3bwIIeQUudfr_CkLMjCE19ECD-U"
+        );
+        jwks.check_pins(&thumbprints)?;
+
+        match jwks.check_pins(&thumbprints[..1]) {
+            Err(Error::UnpinnedKey(thumbprint)) => assert_eq!(thumbprint, thumbprints[1]),
+            other => panic!("Unpinned key should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn snapshot() -> Result<(), Error> {
+        let snapshot = JwkSnapshot::new(jwks(), 1000);
+        let json = serde_json::to_value(&snapshot)?;
+        assert_eq!(json["fetched_at"], 1000);
+        assert_eq!(serde_json::from_value::<JwkSet>(json)?, jwks());
+
+        #[cfg(feature = "std")]
+        {
+            let path = std::env::temp_dir().join(format!("jwt-jwks-{}.json", std::process::id()));
+            snapshot.save(&path)?;
+            let loaded = JwkSnapshot::load(&path);
+            std::fs::remove_file(&path)?;
+            assert_eq!(loaded?, snapshot);
+        }
+        assert_eq!(snapshot.age(1500), 500);
+        assert_eq!(snapshot.age(500), 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn hmac_verifying_store() -> Result<(), Error> {
+        use crate::algorithm::{from_secret, AlgorithmType};
+        use crate::{Header, SignWithKey, Store, Token, VerifyWithStore};
+        use std::collections::BTreeMap;
+
+        let mut jwks = jwks();
+        jwks.keys[1].insert("alg".into(), "HS256".into());
+        let store = jwks.verifying_store()?;
+        assert!(store.get("second").is_some());
+
+        let header = Header {
+            algorithm: AlgorithmType::Hs256,
+            key_id: Some("second".into()),
+            ..Default::default()
+        };
+        let key = from_secret(b"a-secret-which-is-32-bytes-long!", AlgorithmType::Hs256)?;
+        let token = Token::new(header, BTreeMap::from([("sub", "someone")])).sign_with_key(&key)?;
+        let claims: BTreeMap<String, String> = token.as_str().verify_with_store(&store)?;
+        assert_eq!(claims["sub"], "someone");
+
+        let thumbprints = jwks.thumbprints()?;
+        let store = jwks.verifying_store_with_pins(&thumbprints)?;
+        assert!(store.get("second").is_some());
+        match jwks.verifying_store_with_pins(&thumbprints[1..]) {
+            Err(Error::UnpinnedKey(thumbprint)) => assert_eq!(thumbprint, thumbprints[0]),
+            other => panic!(
+                "Unpinned keys should not be loaded: {:?}",
+                other.map(|_| ())
+            ),
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    pub fn verifying_store() -> Result<(), Error> {
+        use crate::algorithm::key_id::KeyIdSource;
+        use crate::algorithm::openssl::PKeyWithDigest;
+        use crate::algorithm::AlgorithmType;
+        use crate::{Header, SignWithKey, Store, Token, VerifyWithStore};
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+        use std::collections::BTreeMap;
+
+        let private_key = PKeyWithDigest {
+            digest: MessageDigest::sha256(),
+            key: PKey::private_key_from_pem(include_bytes!("../test/es256-private.pem"))?,
+        };
+        let mut jwk = private_key.public_key()?.public_jwk()?;
+        jwk.insert("kid".into(), "signing".into());
+        jwk.insert("alg".into(), "ES256".into());
+        let mut encryption = jwk.clone();
+        encryption.insert("kid".into(), "encryption".into());
+        encryption.insert("use".into(), "enc".into());
+        let mut ed25519 = jwk.clone();
+        ed25519.insert("kid".into(), "ed25519".into());
+        ed25519.insert("alg".into(), "EdDSA".into());
+
+        let mut jwks = jwks();
+        jwks.keys.extend([jwk, encryption, ed25519]);
+        let store = jwks.verifying_store()?;
+        assert!(store.get("signing").is_some());
+        assert!(store.get("encryption").is_none());
+        assert!(store.get("ed25519").is_none());
+        assert!(store.get("first").is_none());
+
+        let header = Header {
+            algorithm: AlgorithmType::Es256,
+            key_id: Some("signing".into()),
+            ..Default::default()
+        };
+        let token =
+            Token::new(header, BTreeMap::from([("sub", "someone")])).sign_with_key(&private_key)?;
+        let claims: BTreeMap<String, String> = token.as_str().verify_with_store(&store)?;
+        assert_eq!(claims["sub"], "someone");
+        Ok(())
+    }
+}
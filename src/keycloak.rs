@@ -0,0 +1,145 @@
+//! The roles in tokens issued by [Keycloak](https://www.keycloak.org): realm
+//! roles in the `realm_access` claim, and the roles of each client in the
+//! `resource_access` claim. The structs can be read from `Claims`, or used as
+//! fields of an application's own claims struct.
+//! ## Examples
+//! ```
+//! use jwt::{Claims, FromBase64};
+//!
+//! // {"realm_access":{"roles":["user"]},"resource_access":{"app":{"roles":["admin"]}}}
+//! let claims = Claims::from_base64(
+//!     "eyJyZWFsbV9hY2Nlc3MiOnsicm9sZXMiOlsidXNlciJdfSwicmVzb3VyY2VfYWNjZXNzIjp7ImFwcCI6eyJyb2xlcyI6WyJhZG1pbiJdfX19",
+//! )
+//! .unwrap();
+//!
+//! assert!(claims.realm_access().unwrap().has_role("user"));
+//!
+//! let resource_access = claims.resource_access().unwrap();
+//! assert!(resource_access.has_client_role("app", "admin"));
+//! assert!(!resource_access.has_client_role("other-app", "admin"));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::claims::Claims;
+use crate::error::Error;
+use crate::scopes::Scopes;
+
+/// The roles granted in a realm, or to a single client.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RealmAccess {
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl RealmAccess {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// The roles as a set, to check several at once.
+    pub fn scopes(&self) -> Scopes {
+        self.roles.iter().cloned().collect()
+    }
+}
+
+/// The roles granted to each client, by client id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceAccess(pub BTreeMap<String, RealmAccess>);
+
+impl ResourceAccess {
+    /// The roles of `client`, if the token grants any.
+    pub fn client(&self, client: &str) -> Option<&RealmAccess> {
+        self.0.get(client)
+    }
+
+    /// The roles of `client`, empty if the token grants none.
+    pub fn client_roles(&self, client: &str) -> &[String] {
+        self.client(client)
+            .map(|access| access.roles.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn has_client_role(&self, client: &str, role: &str) -> bool {
+        self.client(client)
+            .is_some_and(|access| access.has_role(role))
+    }
+}
+
+impl Claims {
+    /// The realm roles of the `realm_access` claim. A missing claim results
+    /// in no roles.
+    pub fn realm_access(&self) -> Result<RealmAccess, Error> {
+        Ok(self.get_private("realm_access")?.unwrap_or_default())
+    }
+
+    /// The client roles of the `resource_access` claim. A missing claim
+    /// results in no roles.
+    pub fn resource_access(&self) -> Result<ResourceAccess, Error> {
+        Ok(self.get_private("resource_access")?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::keycloak::{RealmAccess, ResourceAccess};
+
+    #[test]
+    pub fn roles() -> Result<(), Error> {
+        let claims: Claims = serde_json::from_value(json!({
+            "sub": "someone",
+            "realm_access": {"roles": ["offline_access", "user"]},
+            "resource_access": {
+                "app": {"roles": ["admin", "viewer"]},
+                "account": {"roles": ["manage-account"]},
+            },
+        }))?;
+
+        let realm_access = claims.realm_access()?;
+        assert!(realm_access.has_role("user"));
+        assert!(!realm_access.has_role("admin"));
+        assert!(realm_access.scopes().has_all(["offline_access", "user"]));
+
+        let resource_access = claims.resource_access()?;
+        assert_eq!(resource_access.client_roles("app"), ["admin", "viewer"]);
+        assert!(resource_access.has_client_role("account", "manage-account"));
+        assert!(!resource_access.has_client_role("account", "admin"));
+        assert!(resource_access.client_roles("missing").is_empty());
+
+        assert_eq!(Claims::default().realm_access()?, RealmAccess::default());
+        Ok(())
+    }
+
+    #[test]
+    pub fn custom_claims() -> Result<(), Error> {
+        #[derive(Deserialize)]
+        struct KeycloakClaims {
+            #[serde(flatten)]
+            registered: RegisteredClaims,
+            realm_access: RealmAccess,
+            #[serde(default)]
+            resource_access: ResourceAccess,
+        }
+
+        let claims: KeycloakClaims = serde_json::from_value(json!({
+            "iss": "https://keycloak.example.com/realms/main",
+            "realm_access": {},
+        }))?;
+        assert!(claims.registered.issuer.is_some());
+        assert!(claims.realm_access.roles.is_empty());
+        assert!(!claims.resource_access.has_client_role("app", "admin"));
+
+        let claims: Claims = serde_json::from_value(json!({"realm_access": {"roles": "admin"}}))?;
+        assert!(claims.realm_access().is_err());
+        Ok(())
+    }
+}
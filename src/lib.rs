@@ -89,15 +89,26 @@
 //! # try_main().unwrap()
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// Lets the code generated by the derive macros name this crate in its tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as jwt;
+
 #[cfg(doctest)]
 doctest!("../README.md");
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt;
 
 #[cfg(doctest)]
 use doc_comment::doctest;
 use serde::{Deserialize, Serialize};
 
+use crate::encoding::Engine;
+
 #[cfg(feature = "openssl")]
 pub use crate::algorithm::openssl::PKeyWithDigest;
 pub use crate::algorithm::store::Store;
@@ -108,18 +119,56 @@ pub use crate::error::Error;
 pub use crate::header::{Header, JoseHeader};
 pub use crate::token::signed::{SignWithKey, SignWithStore};
 pub use crate::token::verified::{VerifyWithKey, VerifyWithStore};
-pub use crate::token::{Unsigned, Unverified, Verified};
+pub use crate::token::{
+    ClaimsAccess, SignatureValid, Unsigned, Unverified, UnverifiedOwned, Validated, Verified,
+};
 
+pub mod access_token;
 pub mod algorithm;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod cache;
 pub mod claims;
+pub mod clock;
+pub mod codec;
+pub mod cognito;
+#[cfg(feature = "std")]
+pub mod config;
+pub mod confirmation;
+pub mod credential;
+pub mod dangerous;
+pub mod encoding;
 pub mod error;
+pub mod extract;
 pub mod header;
+mod instrument;
+#[cfg(feature = "tonic")]
+pub mod interceptor;
+pub mod jwks;
+pub mod keycloak;
+pub mod oidc;
+pub mod payload;
+pub mod principal;
+pub mod raw;
+pub mod scopes;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod token;
+pub mod validation;
+pub mod webhook;
 
 const SEPARATOR: &str = ".";
 
 /// Representation of a structured JWT. Methods vary based on the signature
-/// type `S`.
+/// type `S`. Every signature state is `Clone`, `Debug`, and `PartialEq`, so
+/// tokens are whenever their header and claims are. With the `redact`
+/// feature, `Debug` hides the claims and signature of token strings.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Token<H, C, S> {
     header: H,
     claims: C,
@@ -130,7 +179,12 @@ impl<H, C, S> Token<H, C, S> {
     pub fn header(&self) -> &H {
         &self.header
     }
+}
 
+/// The claims of unverified tokens can only be read through
+/// [dangerous](dangerous/index.html), unless the `unverified-claims` feature
+/// is enabled.
+impl<H, C, S: ClaimsAccess> Token<H, C, S> {
     pub fn claims(&self) -> &C {
         &self.claims
     }
@@ -144,7 +198,17 @@ impl<H, C, S> Token<H, C, S> {
     }
 }
 
-impl<H, C, S> From<Token<H, C, S>> for (H, C) {
+impl<H: fmt::Debug, C: fmt::Debug, S: fmt::Debug> fmt::Debug for Token<H, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("header", &self.header)
+            .field("claims", &self.claims)
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+impl<H, C, S: ClaimsAccess> From<Token<H, C, S>> for (H, C) {
     fn from(token: Token<H, C, S>) -> Self {
         (token.header, token.claims)
     }
@@ -157,14 +221,27 @@ impl<H, C, S> From<Token<H, C, S>> for (H, C) {
 /// [Serialize](../../serde/trait.Serialize.html). as a base64 encoding of
 /// the object's JSON representation.
 pub trait ToBase64 {
-    fn to_base64(&self) -> Result<Cow<str>, Error>;
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error>;
+
+    /// Append the base64 encoding to `output`. Implementations can override
+    /// this to encode directly into the buffer without an intermediate
+    /// string.
+    fn append_base64(&self, output: &mut String) -> Result<(), Error> {
+        output.push_str(&self.to_base64()?);
+        Ok(())
+    }
 }
 
 impl<T: Serialize> ToBase64 for T {
-    fn to_base64(&self) -> Result<Cow<str>, Error> {
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        let json_bytes = serde_json::to_vec(&self)?;
+        Ok(Cow::Owned(encoding::encode(json_bytes)))
+    }
+
+    fn append_base64(&self, output: &mut String) -> Result<(), Error> {
         let json_bytes = serde_json::to_vec(&self)?;
-        let encoded_json_bytes = base64::encode_config(&json_bytes, base64::URL_SAFE_NO_PAD);
-        Ok(Cow::Owned(encoded_json_bytes))
+        encoding::encode_into(json_bytes, output);
+        Ok(())
     }
 }
 
@@ -176,15 +253,62 @@ impl<T: Serialize> ToBase64 for T {
 /// the base64 encoded JSON representation.
 pub trait FromBase64: Sized {
     fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error>;
+
+    /// Parse a component decoded with a different engine, such as
+    /// [Tolerant](encoding/struct.Tolerant.html). By default the component is
+    /// re-encoded in the standard way and parsed with `from_base64`.
+    fn from_base64_with<Input: ?Sized + AsRef<[u8]>>(
+        raw: &Input,
+        engine: &impl Engine,
+    ) -> Result<Self, Error> {
+        let bytes = engine.decode(raw.as_ref())?;
+        Self::from_base64(&encoding::encode(bytes))
+    }
 }
 
 impl<T: for<'de> Deserialize<'de> + Sized> FromBase64 for T {
     fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error> {
-        let json_bytes = base64::decode_config(raw, base64::URL_SAFE_NO_PAD)?;
-        Ok(serde_json::from_slice(&json_bytes)?)
+        let json_bytes = encoding::decode(raw)?;
+        from_json(&json_bytes)
+    }
+
+    fn from_base64_with<Input: ?Sized + AsRef<[u8]>>(
+        raw: &Input,
+        engine: &impl Engine,
+    ) -> Result<Self, Error> {
+        let json_bytes = engine.decode(raw.as_ref())?;
+        from_json(&json_bytes)
     }
 }
 
+/// Deserialize JSON, recording the path of the member that failed to
+/// deserialize in `Error::JsonAt` if the `json-path` feature is enabled.
+#[cfg(feature = "json-path")]
+fn from_json<T: for<'de> Deserialize<'de>>(json_bytes: &[u8]) -> Result<T, Error> {
+    use alloc::string::ToString;
+
+    let mut deserializer = serde_json::Deserializer::from_slice(json_bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        // Syntax errors and errors of the whole value have no meaningful path.
+        if e.inner().is_data() && e.path().iter().next().is_some() {
+            Error::JsonAt {
+                component: None,
+                path: Some(e.path().to_string()),
+                source: e.into_inner(),
+            }
+        } else {
+            Error::Json(e.into_inner())
+        }
+    })?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+#[cfg(not(feature = "json-path"))]
+fn from_json<T: for<'de> Deserialize<'de>>(json_bytes: &[u8]) -> Result<T, Error> {
+    Ok(serde_json::from_slice(json_bytes)?)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::algorithm::AlgorithmType::Hs256;
@@ -221,8 +345,35 @@ mod tests {
         let recreated_token: Token<Header, Claims, _> = Token::parse_unverified(signed_token_str)?;
 
         assert_eq!(signed_token.header(), recreated_token.header());
-        assert_eq!(signed_token.claims(), recreated_token.claims());
+        assert_eq!(
+            signed_token.claims(),
+            crate::dangerous::unverified_claims(&recreated_token)
+        );
         recreated_token.verify_with_key(&key)?;
         Ok(())
     }
+
+    #[test]
+    pub fn clone_and_compare() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let token: Token<Header, Claims, _> = Default::default();
+        assert_eq!(token.clone(), token);
+
+        let signed = token.sign_with_key(&key)?;
+        assert_eq!(signed.clone(), signed);
+
+        let unverified: Token<Header, Claims, _> = Token::parse_unverified(signed.as_str())?;
+        let copy = unverified.clone();
+        assert_eq!(copy, unverified);
+        assert_eq!(unverified.clone().into_owned(), copy.into_owned());
+
+        let verified: Token<Header, Claims, _> = unverified.verify_with_key(&key)?;
+        assert_eq!(verified.clone(), verified);
+        assert!(format!("{:?}", verified).contains("Verified"));
+
+        let other: Token<Header, Claims, _> = Token::new(Header::default(), Claims::default())
+            .sign_with_key(&Hmac::<Sha256>::new_from_slice(b"other")?)?;
+        assert_ne!(other, signed);
+        Ok(())
+    }
 }
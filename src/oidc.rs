@@ -0,0 +1,174 @@
+//! [OpenID Connect Discovery](https://openid.net/specs/openid-connect-discovery-1_0.html)
+//! documents, which name the JWK set and the signing algorithms of an
+//! issuer. The crate makes no HTTP requests itself: fetch the document from
+//! [discovery_url](fn.discovery_url.html) and the JWK set from its
+//! `jwks_uri` with the application's HTTP client, and pass them here.
+//! ## Examples
+//! ```
+//! use jwt::oidc::{discovery_url, OidcDiscovery};
+//! use jwt::AlgorithmType;
+//!
+//! let issuer = "https://accounts.example.com";
+//! assert_eq!(
+//!     discovery_url(issuer),
+//!     "https://accounts.example.com/.well-known/openid-configuration"
+//! );
+//!
+//! let document = r#"{
+//!     "issuer": "https://accounts.example.com",
+//!     "jwks_uri": "https://accounts.example.com/jwks.json",
+//!     "id_token_signing_alg_values_supported": ["RS256", "EdDSA"]
+//! }"#;
+//! let discovery = OidcDiscovery::parse(document, issuer).unwrap();
+//! assert_eq!(discovery.jwks_uri, "https://accounts.example.com/jwks.json");
+//! assert_eq!(discovery.algorithms(), [AlgorithmType::Rs256]);
+//!
+//! let validation = discovery.validation().audience("my-client-id");
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::AlgorithmType;
+use crate::error::Error;
+use crate::jwks::JwkSet;
+use crate::validation::Validation;
+
+/// The URL of the discovery document of `issuer`.
+pub fn discovery_url(issuer: &str) -> String {
+    alloc::format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    )
+}
+
+/// The members of a discovery document that concern verifying tokens.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OidcDiscovery {
+    pub issuer: String,
+    pub jwks_uri: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub userinfo_endpoint: Option<String>,
+}
+
+impl OidcDiscovery {
+    /// Parse the discovery document fetched for `issuer`. As the
+    /// specification requires, the document must name that same issuer, or
+    /// parsing fails with `Error::UnknownIssuer`.
+    pub fn parse(document: &str, issuer: &str) -> Result<Self, Error> {
+        let discovery: OidcDiscovery = serde_json::from_str(document)?;
+        if discovery.issuer != issuer {
+            return Err(Error::UnknownIssuer(discovery.issuer));
+        }
+        Ok(discovery)
+    }
+
+    /// The supported signing algorithms that this crate implements, without
+    /// `none`.
+    pub fn algorithms(&self) -> Vec<AlgorithmType> {
+        self.id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|algorithm| serde_json::from_value(algorithm.as_str().into()).ok())
+            .filter(|algorithm| *algorithm != AlgorithmType::None)
+            .collect()
+    }
+
+    /// A validation of tokens from the issuer, to which the audience and
+    /// other checks can be added.
+    pub fn validation(&self) -> Validation {
+        Validation::new().issuer(self.issuer.as_str())
+    }
+
+    /// The keys of `jwks` whose `alg` is one of the issuer's supported
+    /// signing algorithms. A key that does not declare its algorithm is kept.
+    pub fn signing_keys(&self, jwks: &JwkSet) -> JwkSet {
+        let keys = jwks
+            .keys
+            .iter()
+            .filter(|jwk| match jwk.get("alg").and_then(|alg| alg.as_str()) {
+                Some(algorithm) => self
+                    .id_token_signing_alg_values_supported
+                    .iter()
+                    .any(|supported| supported == algorithm),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        JwkSet { keys }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::algorithm::AlgorithmType;
+    use crate::claims::RegisteredClaims;
+    use crate::error::Error;
+    use crate::jwks::JwkSet;
+    use crate::oidc::{discovery_url, OidcDiscovery};
+
+    const ISSUER: &str = "https://accounts.example.com";
+
+    fn document() -> String {
+        json!({
+            "issuer": ISSUER,
+            "jwks_uri": "https://accounts.example.com/jwks.json",
+            "id_token_signing_alg_values_supported": ["ES256", "RS256", "none", "EdDSA"],
+            "response_types_supported": ["code"],
+        })
+        .to_string()
+    }
+
+    #[test]
+    pub fn discovery() -> Result<(), Error> {
+        assert_eq!(
+            discovery_url(&format!("{}/", ISSUER)),
+            discovery_url(ISSUER)
+        );
+
+        let discovery = OidcDiscovery::parse(&document(), ISSUER)?;
+        assert_eq!(
+            discovery.algorithms(),
+            [AlgorithmType::Es256, AlgorithmType::Rs256]
+        );
+        assert_eq!(discovery.token_endpoint, None);
+
+        let claims = RegisteredClaims {
+            issuer: Some("https://attacker.example.com".into()),
+            ..Default::default()
+        };
+        assert!(discovery.validation().validate_at(&claims, 0).is_err());
+
+        match OidcDiscovery::parse(&document(), "https://other.example.com") {
+            Err(Error::UnknownIssuer(issuer)) => assert_eq!(issuer, ISSUER),
+            other => panic!(
+                "Document of another issuer should not be accepted: {:?}",
+                other
+            ),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn signing_keys() -> Result<(), Error> {
+        let discovery = OidcDiscovery::parse(&document(), ISSUER)?;
+        let jwks: JwkSet = serde_json::from_value(json!({"keys": [
+            {"kid": "es256", "alg": "ES256"},
+            {"kid": "hs256", "alg": "HS256"},
+            {"kid": "undeclared"},
+        ]}))?;
+
+        let keys = discovery.signing_keys(&jwks);
+        assert!(keys.get("es256").is_some());
+        assert!(keys.get("hs256").is_none());
+        assert!(keys.get("undeclared").is_some());
+        Ok(())
+    }
+}
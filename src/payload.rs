@@ -0,0 +1,214 @@
+//! Payloads that are not JSON. JWS can sign any sequence of bytes, and some
+//! protocols sign opaque binary payloads, or none at all, in the compact form
+//! of a token. A `BytesPayload` is encoded as plain base64 of its bytes.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::payload::BytesPayload;
+//! use jwt::{Header, SignWithKey, Token, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let payload = BytesPayload::new(vec![0xde, 0xad, 0xbe, 0xef]);
+//! let token = Token::new(Header::default(), payload).sign_with_key(&key).unwrap();
+//! assert!(token.as_str().starts_with("eyJhbGciOiJIUzI1NiJ9.3q2-7w."));
+//!
+//! let token: Token<Header, BytesPayload, _> = token.as_str().verify_with_key(&key).unwrap();
+//! assert_eq!(token.claims().as_bytes(), [0xde, 0xad, 0xbe, 0xef]);
+//! ```
+//!
+//! Tokens built with `Token::text` or `Token::octet_stream` declare the type
+//! of their payload in `cty`, which is checked when the payload is read back.
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::payload::BytesPayload;
+//! use jwt::{Header, SignWithKey, Token, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let token = Token::text(Header::default(), "hello").sign_with_key(&key).unwrap();
+//!
+//! let token: Token<Header, BytesPayload, _> = token.as_str().verify_with_key(&key).unwrap();
+//! assert_eq!(token.into_text().unwrap(), "hello");
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::encoding::{self, Engine};
+use crate::error::Error;
+use crate::header::{Header, HeaderContentType, JoseHeader};
+use crate::token::{Unsigned, Verified};
+use crate::validation::check_header_value;
+use crate::{FromBase64, ToBase64, Token};
+
+/// An opaque payload, signed as its raw bytes rather than as JSON. An empty
+/// payload produces a token with an empty claims component.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BytesPayload(pub Vec<u8>);
+
+impl BytesPayload {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        BytesPayload(bytes.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for BytesPayload {
+    fn from(bytes: Vec<u8>) -> Self {
+        BytesPayload(bytes)
+    }
+}
+
+impl From<BytesPayload> for Vec<u8> {
+    fn from(payload: BytesPayload) -> Self {
+        payload.0
+    }
+}
+
+impl AsRef<[u8]> for BytesPayload {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl ToBase64 for BytesPayload {
+    fn to_base64(&self) -> Result<Cow<'_, str>, Error> {
+        Ok(Cow::Owned(encoding::encode(&self.0)))
+    }
+
+    fn append_base64(&self, output: &mut String) -> Result<(), Error> {
+        encoding::encode_into(&self.0, output);
+        Ok(())
+    }
+}
+
+impl FromBase64 for BytesPayload {
+    fn from_base64<Input: ?Sized + AsRef<[u8]>>(raw: &Input) -> Result<Self, Error> {
+        Ok(BytesPayload(encoding::decode(raw)?))
+    }
+
+    fn from_base64_with<Input: ?Sized + AsRef<[u8]>>(
+        raw: &Input,
+        engine: &impl Engine,
+    ) -> Result<Self, Error> {
+        Ok(BytesPayload(engine.decode(raw.as_ref())?))
+    }
+}
+
+impl Token<Header, BytesPayload, Unsigned> {
+    /// A token of `payload`, with `cty` set to `content_type`.
+    pub fn with_content_type(
+        mut header: Header,
+        content_type: HeaderContentType,
+        payload: BytesPayload,
+    ) -> Self {
+        header.content_type = Some(content_type);
+        Token::new(header, payload)
+    }
+
+    /// A token of UTF-8 `text`, with `cty` set to `text/plain`.
+    pub fn text(header: Header, text: impl Into<String>) -> Self {
+        let payload = BytesPayload::new(text.into());
+        Self::with_content_type(header, HeaderContentType::TextPlain, payload)
+    }
+
+    /// A token of arbitrary `bytes`, with `cty` set to `octet-stream`.
+    pub fn octet_stream(header: Header, bytes: impl Into<Vec<u8>>) -> Self {
+        let payload = BytesPayload::new(bytes);
+        Self::with_content_type(header, HeaderContentType::OctetStream, payload)
+    }
+}
+
+impl<H: JoseHeader> Token<H, BytesPayload, Verified> {
+    /// Return the token if its `cty` is `expected`, and fail with
+    /// `Error::InvalidClaim` or `Error::MissingClaim` otherwise.
+    pub fn expect_content_type(self, expected: &HeaderContentType) -> Result<Self, Error> {
+        check_header_value("cty", self.header.content_type(), expected)?;
+        Ok(self)
+    }
+
+    /// The payload of a `text/plain` token.
+    pub fn into_text(self) -> Result<String, Error> {
+        let token = self.expect_content_type(&HeaderContentType::TextPlain)?;
+        Ok(String::from_utf8(token.claims.0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::header::{Header, HeaderContentType};
+    use crate::payload::BytesPayload;
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::{split_components, VerifyWithKey};
+    use crate::Token;
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let bytes: Vec<u8> = (0..=255).collect();
+        let token = Token::new(Header::default(), BytesPayload::new(bytes.clone()));
+        let token_str = String::from(token.sign_with_key(&key)?);
+
+        let verified: Token<Header, BytesPayload, _> = token_str.as_str().verify_with_key(&key)?;
+        assert_eq!(verified.claims().as_bytes(), &bytes[..]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn empty_payload() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let token = Token::new(Header::default(), BytesPayload::default()).sign_with_key(&key)?;
+        let [header, claims, _] = split_components(token.as_str())?;
+        assert_eq!(header, "eyJhbGciOiJIUzI1NiJ9");
+        assert_eq!(claims, "");
+
+        let verified: Token<Header, BytesPayload, _> = token.as_str().verify_with_key(&key)?;
+        assert!(verified.claims().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn content_types() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let manifest = Token::octet_stream(Header::default(), vec![0xff, 0xfe]);
+        let manifest = String::from(manifest.sign_with_key(&key)?);
+        let text = Token::text(Header::default(), "hello");
+        let text = String::from(text.sign_with_key(&key)?);
+
+        let verify = |token_str: &str| -> Result<Token<Header, BytesPayload, _>, Error> {
+            token_str.verify_with_key(&key)
+        };
+        assert_eq!(verify(&text)?.into_text()?, "hello");
+        verify(&manifest)?.expect_content_type(&HeaderContentType::OctetStream)?;
+
+        match verify(&manifest)?.into_text() {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "cty"),
+            other => panic!("Wrong content type should not be accepted: {:?}", other),
+        }
+
+        let untyped = Token::new(Header::default(), BytesPayload::new("hello"));
+        let untyped = String::from(untyped.sign_with_key(&key)?);
+        match verify(&untyped)?.into_text() {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "cty"),
+            other => panic!("Missing content type should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+}
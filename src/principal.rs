@@ -0,0 +1,210 @@
+//! The identity a token was issued for. Tokens for users usually carry a
+//! `sub` claim, while tokens for machines from the client credentials grant
+//! may only carry `client_id`, and some issuers use their own claim. A
+//! `PrincipalExtractor` looks for each of these in order of priority.
+//! ## Examples
+//! ```
+//! use jwt::principal::{PrincipalExtractor, PrincipalSource};
+//! use jwt::{Claims, FromBase64};
+//!
+//! // {"client_id":"billing-service","ext":{"account":"acme"}}
+//! let claims =
+//!     Claims::from_base64("eyJjbGllbnRfaWQiOiJiaWxsaW5nLXNlcnZpY2UiLCJleHQiOnsiYWNjb3VudCI6ImFjbWUifX0")
+//!         .unwrap();
+//!
+//! let principal = PrincipalExtractor::new().extract(&claims).unwrap();
+//! assert_eq!(principal.source, PrincipalSource::ClientId);
+//! assert_eq!(principal.id, "billing-service");
+//!
+//! let principal = PrincipalExtractor::from_sources(vec![PrincipalSource::Claim("/ext/account".into())])
+//!     .extract(&claims)
+//!     .unwrap();
+//! assert_eq!(principal.id, "acme");
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+use crate::access_token::AccessTokenClaims;
+use crate::claims::{pointer_in, Claims};
+use crate::error::Error;
+use crate::token::Validated;
+use crate::Token;
+
+/// Where the identity of a principal was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrincipalSource {
+    /// The `sub` claim.
+    Subject,
+    /// The `client_id` claim.
+    ClientId,
+    /// A claim, given as a JSON pointer such as `/ext/account`.
+    Claim(String),
+}
+
+/// The identity a token was issued for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Principal {
+    pub source: PrincipalSource,
+    pub id: String,
+}
+
+/// Claims that a principal can be extracted from.
+pub trait PrincipalClaims {
+    fn subject(&self) -> Option<&str>;
+
+    fn client_id(&self) -> Option<&str>;
+
+    /// The claim at a JSON pointer, where the first segment names a
+    /// top-level claim.
+    fn claim(&self, pointer: &str) -> Option<&Value>;
+}
+
+impl PrincipalClaims for Claims {
+    fn subject(&self) -> Option<&str> {
+        self.registered.subject.as_deref()
+    }
+
+    fn client_id(&self) -> Option<&str> {
+        self.private.get("client_id").and_then(Value::as_str)
+    }
+
+    fn claim(&self, pointer: &str) -> Option<&Value> {
+        pointer_in(&self.private, pointer)
+    }
+}
+
+impl PrincipalClaims for AccessTokenClaims {
+    fn subject(&self) -> Option<&str> {
+        self.registered.subject.as_deref()
+    }
+
+    fn client_id(&self) -> Option<&str> {
+        self.client_id.as_deref()
+    }
+
+    fn claim(&self, pointer: &str) -> Option<&Value> {
+        pointer_in(&self.private, pointer)
+    }
+}
+
+/// Extracts a principal from the first of its sources present in the
+/// claims. By default, `sub` is preferred over `client_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrincipalExtractor {
+    sources: Vec<PrincipalSource>,
+}
+
+impl Default for PrincipalExtractor {
+    fn default() -> Self {
+        PrincipalExtractor {
+            sources: vec![PrincipalSource::Subject, PrincipalSource::ClientId],
+        }
+    }
+}
+
+impl PrincipalExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look for a principal in only `sources`, in order.
+    pub fn from_sources(sources: Vec<PrincipalSource>) -> Self {
+        PrincipalExtractor { sources }
+    }
+
+    /// Look in `source` after all of the current sources.
+    pub fn source(mut self, source: PrincipalSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// The principal from the first source present in the claims. String
+    /// and numeric claims are accepted as identities.
+    pub fn extract(&self, claims: &impl PrincipalClaims) -> Result<Principal, Error> {
+        for source in &self.sources {
+            let id = match source {
+                PrincipalSource::Subject => claims.subject().map(ToString::to_string),
+                PrincipalSource::ClientId => claims.client_id().map(ToString::to_string),
+                PrincipalSource::Claim(pointer) => match claims.claim(pointer) {
+                    Some(Value::String(id)) => Some(id.clone()),
+                    Some(Value::Number(id)) => Some(id.to_string()),
+                    Some(_) => return Err(Error::InvalidClaim(pointer.clone())),
+                    None => None,
+                },
+            };
+
+            if let Some(id) = id {
+                return Ok(Principal {
+                    source: source.clone(),
+                    id,
+                });
+            }
+        }
+
+        let name = match self.sources.first() {
+            Some(PrincipalSource::Subject) | None => "sub".into(),
+            Some(PrincipalSource::ClientId) => "client_id".into(),
+            Some(PrincipalSource::Claim(pointer)) => pointer.clone(),
+        };
+        Err(Error::MissingClaim(name))
+    }
+}
+
+impl<H, C: PrincipalClaims> Token<H, C, Validated> {
+    /// The principal of the token, from `sub` or else `client_id`. Only
+    /// validated tokens have one, so that an expired token or one meant for
+    /// another audience cannot be taken for its subject.
+    pub fn principal(&self) -> Result<Principal, Error> {
+        PrincipalExtractor::default().extract(&self.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::principal::{PrincipalExtractor, PrincipalSource};
+
+    #[test]
+    pub fn priority_order() -> Result<(), Error> {
+        let mut claims = Claims::new(RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        });
+        claims.set_private("client_id", "app")?;
+        claims.set_private("oid", json!({ "user": 42 }))?;
+
+        let principal = PrincipalExtractor::new().extract(&claims)?;
+        assert_eq!(principal.source, PrincipalSource::Subject);
+        assert_eq!(principal.id, "someone");
+
+        let oid = PrincipalSource::Claim("/oid/user".into());
+        let principal = PrincipalExtractor::from_sources(vec![oid.clone()])
+            .source(PrincipalSource::Subject)
+            .extract(&claims)?;
+        assert_eq!(principal.source, oid);
+        assert_eq!(principal.id, "42");
+
+        match PrincipalExtractor::from_sources(vec![PrincipalSource::Claim("/oid".into())])
+            .extract(&claims)
+        {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "/oid"),
+            other => panic!("Object should not be an identity: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn missing_principal() {
+        match PrincipalExtractor::new().extract(&Claims::default()) {
+            Err(Error::MissingClaim(name)) => assert_eq!(name, "sub"),
+            other => panic!("Anonymous token should have no principal: {:?}", other),
+        }
+    }
+}
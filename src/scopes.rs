@@ -0,0 +1,181 @@
+//! Authorization helpers for the `scope` claim, a space delimited string as
+//! defined in [RFC 8693](https://tools.ietf.org/html/rfc8693#section-4.2),
+//! and the `roles` and `permissions` claims, which are conventionally arrays
+//! of strings.
+//! ## Examples
+//! ```
+//! use jwt::{Claims, FromBase64};
+//!
+//! // {"scope":"read write","roles":["admin"]}
+//! let claims = Claims::from_base64("eyJzY29wZSI6InJlYWQgd3JpdGUiLCJyb2xlcyI6WyJhZG1pbiJdfQ").unwrap();
+//!
+//! let scopes = claims.scopes().unwrap();
+//! assert!(scopes.has_scope("read"));
+//! assert!(scopes.has_all(["read", "write"]));
+//! assert!(!scopes.has_any(["delete"]));
+//!
+//! assert!(claims.roles().unwrap().has_scope("admin"));
+//! ```
+
+use alloc::collections::btree_set::{self, BTreeSet};
+use alloc::string::{String, ToString};
+use core::iter::FromIterator;
+
+use serde_json::Value;
+
+use crate::claims::Claims;
+use crate::error::Error;
+
+/// A set of scopes, roles, or permissions granted by a token.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scopes(BTreeSet<String>);
+
+impl Scopes {
+    /// Parse a space delimited list of scopes.
+    pub fn parse(scope: &str) -> Self {
+        Scopes(scope.split_whitespace().map(ToString::to_string).collect())
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Whether at least one of `scopes` is granted.
+    pub fn has_any<I, S>(&self, scopes: I) -> bool
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        scopes.into_iter().any(|s| self.has_scope(s.as_ref()))
+    }
+
+    /// Whether every one of `scopes` is granted.
+    pub fn has_all<I, S>(&self, scopes: I) -> bool
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        scopes.into_iter().all(|s| self.has_scope(s.as_ref()))
+    }
+
+    pub fn iter(&self) -> btree_set::Iter<'_, String> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add the scopes in a claim value, which can be either a space delimited
+    /// string or an array of strings.
+    fn extend_from_claim(&mut self, name: &str, value: &Value) -> Result<(), Error> {
+        match value {
+            Value::String(scope) => self.0.extend(Scopes::parse(scope).0),
+            Value::Array(values) => {
+                for value in values {
+                    let scope = value
+                        .as_str()
+                        .ok_or_else(|| Error::InvalidClaim(name.to_string()))?;
+                    self.0.insert(scope.to_string());
+                }
+            }
+            _ => return Err(Error::InvalidClaim(name.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+impl IntoIterator for Scopes {
+    type Item = String;
+    type IntoIter = btree_set::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Scopes {
+    type Item = &'a String;
+    type IntoIter = btree_set::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Scopes {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Scopes(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Claims {
+    /// The scopes granted by the `scope` claim. Missing claims result in an
+    /// empty set.
+    pub fn scopes(&self) -> Result<Scopes, Error> {
+        self.collect_scopes(&["scope"])
+    }
+
+    /// The combined contents of the `roles` and `permissions` claims. Missing
+    /// claims result in an empty set.
+    pub fn roles(&self) -> Result<Scopes, Error> {
+        self.collect_scopes(&["roles", "permissions"])
+    }
+
+    fn collect_scopes(&self, names: &[&str]) -> Result<Scopes, Error> {
+        let mut scopes = Scopes::default();
+        for name in names {
+            if let Some(value) = self.private.get(*name) {
+                scopes.extend_from_claim(name, value)?;
+            }
+        }
+
+        Ok(scopes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::claims::Claims;
+    use crate::error::Error;
+    use crate::scopes::Scopes;
+
+    #[test]
+    pub fn parse() {
+        let scopes = Scopes::parse(" read  write\tadmin ");
+        assert_eq!(scopes.len(), 3);
+        assert!(scopes.has_all(["read", "write", "admin"]));
+        assert!(!scopes.has_scope(""));
+        assert!(Scopes::parse("").is_empty());
+    }
+
+    #[test]
+    pub fn roles_and_permissions() -> Result<(), Error> {
+        let mut claims = Claims::default();
+        claims.set_private("roles", ["admin"])?;
+        claims.set_private("permissions", "read write")?;
+
+        let roles = claims.roles()?;
+        assert!(roles.has_all(["admin", "read", "write"]));
+        assert!(claims.scopes()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    pub fn invalid_claim() {
+        let mut claims = Claims::default();
+        claims.private.insert("scope".into(), json!(["read", 1]));
+
+        match claims.scopes() {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "scope"),
+            other => panic!("Invalid scope claim should not be accepted: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Streaming encoding and decoding of token components, for claims that are
+//! too large to comfortably hold in memory more than once. Claims are
+//! serialized and base64 encoded straight into a writer, and decoded straight
+//! from the token string, without an intermediate JSON buffer.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::{stream, Header, SignWithKey, Token};
+//! use sha2::Sha256;
+//! use std::collections::BTreeMap;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let mut claims = BTreeMap::new();
+//! claims.insert("sub".to_owned(), "someone".to_owned());
+//!
+//! let encoded = stream::to_writer(&claims, Vec::new()).unwrap();
+//! assert_eq!(encoded, b"eyJzdWIiOiJzb21lb25lIn0");
+//!
+//! let token_str = claims.sign_with_key(&key).unwrap();
+//! let token: Token<Header, BTreeMap<String, String>, _> =
+//!     stream::verify_with_key(&token_str, &key).unwrap();
+//! assert_eq!(token.claims()["sub"], "someone");
+//! ```
+
+use std::io::Write;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::algorithm::VerifyingAlgorithm;
+use crate::encoding;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::verified::split_components;
+use crate::token::Verified;
+use crate::{FromBase64, Token};
+
+/// Serialize `value` as JSON and base64 encode it into `writer`, returning
+/// the writer once all of the output has been written.
+pub fn to_writer<T, W>(value: &T, writer: W) -> Result<W, Error>
+where
+    T: Serialize + ?Sized,
+    W: Write,
+{
+    let mut encoder = base64::write::EncoderWriter::new(writer, encoding::CONFIG);
+    serde_json::to_writer(&mut encoder, value)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decode a base64 encoded JSON component, deserializing while decoding.
+pub fn from_segment<T: DeserializeOwned>(segment: &str) -> Result<T, Error> {
+    let mut bytes = segment.as_bytes();
+    let decoder = base64::read::DecoderReader::new(&mut bytes, encoding::CONFIG);
+    Ok(serde_json::from_reader(decoder)?)
+}
+
+/// Verify a token string with a key. The signature is checked against the
+/// raw components of the token string before the claims are decoded, and
+/// the claims are then decoded with [from_segment](fn.from_segment.html).
+pub fn verify_with_key<H, C>(
+    token_str: &str,
+    key: &impl VerifyingAlgorithm,
+) -> Result<Token<H, C, Verified>, Error>
+where
+    H: FromBase64 + JoseHeader,
+    C: DeserializeOwned,
+{
+    let [header_str, claims_str, signature_str] = split_components(token_str)?;
+    let header = H::from_base64(header_str)?;
+
+    let header_algorithm = header.algorithm_type();
+    if !key.accepts(header_algorithm) {
+        let key_algorithm = key.algorithm_type();
+        return Err(Error::AlgorithmMismatch(header_algorithm, key_algorithm));
+    }
+
+    if !key.verify(header_str, claims_str, signature_str)? {
+        return Err(Error::InvalidSignature);
+    }
+
+    let claims = from_segment(claims_str)?;
+
+    Ok(Token {
+        header,
+        claims,
+        signature: Verified,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::stream::{from_segment, to_writer, verify_with_key};
+    use crate::token::signed::SignWithKey;
+    use crate::{ToBase64, Token};
+
+    fn large_claims() -> BTreeMap<String, String> {
+        (0..10_000)
+            .map(|i| (format!("claim_{}", i), "x".repeat(i % 64)))
+            .collect()
+    }
+
+    #[test]
+    pub fn roundtrip() -> Result<(), Error> {
+        let claims = large_claims();
+        let encoded = to_writer(&claims, Vec::new())?;
+
+        assert_eq!(encoded, claims.to_base64()?.as_bytes());
+        let decoded: BTreeMap<String, String> = from_segment(&String::from_utf8(encoded)?)?;
+        assert_eq!(decoded, claims);
+        Ok(())
+    }
+
+    #[test]
+    pub fn verify_large_claims() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let claims = large_claims();
+        let token_str: String = (&claims).sign_with_key(&key)?;
+
+        let token: Token<Header, BTreeMap<String, String>, _> = verify_with_key(&token_str, &key)?;
+        assert_eq!(token.claims(), &claims);
+
+        let other_key: Hmac<Sha256> = Hmac::new_from_slice(b"other")?;
+        let result: Result<Token<Header, BTreeMap<String, String>, _>, _> =
+            verify_with_key(&token_str, &other_key);
+        assert!(result.is_err());
+        Ok(())
+    }
+}
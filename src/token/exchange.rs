@@ -0,0 +1,453 @@
+//! Exchange of verified tokens for newly signed ones, as done by a security
+//! token service. Inbound tokens are verified and validated, their claims
+//! are mapped to the outbound claims, and the result is signed with a fresh
+//! header. When the exchanger acts on behalf of the subject, it adds itself
+//! to the chain of actors in the `act` claim, as described in
+//! [RFC 8693](https://tools.ietf.org/html/rfc8693#section-4.1).
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::token::exchange::{Actor, Exchanged, TokenExchanger};
+//! use jwt::validation::Validation;
+//! use jwt::{Claims, RegisteredClaims, SignWithKey, VerifyWithKey};
+//! use sha2::Sha256;
+//!
+//! # use jwt::Error;
+//! # fn try_main() -> Result<(), Error> {
+//! let inbound_key: Hmac<Sha256> = Hmac::new_from_slice(b"inbound")?;
+//! let outbound_key: Hmac<Sha256> = Hmac::new_from_slice(b"outbound")?;
+//!
+//! let exchanger = TokenExchanger::new(
+//!     inbound_key.clone(),
+//!     Validation::new().issuer("idp.example.com"),
+//!     outbound_key.clone(),
+//!     |claims: Claims| {
+//!         Ok(RegisteredClaims {
+//!             issuer: Some("sts.example.com".into()),
+//!             subject: claims.registered.subject,
+//!             audience: Some("backend".into()),
+//!             ..Default::default()
+//!         })
+//!     },
+//! )
+//! .actor(Actor::new("gateway"));
+//!
+//! let inbound = RegisteredClaims {
+//!     issuer: Some("idp.example.com".into()),
+//!     subject: Some("someone".into()),
+//!     ..Default::default()
+//! }
+//! .sign_with_key(&inbound_key)?;
+//!
+//! let outbound = exchanger.exchange(&inbound)?;
+//! let claims: Exchanged<RegisteredClaims> = outbound.as_str().verify_with_key(&outbound_key)?;
+//! assert_eq!(claims.claims.subject.as_deref(), Some("someone"));
+//! assert_eq!(claims.actor.unwrap().subject, "gateway");
+//! # Ok(())
+//! # }
+//! # try_main().unwrap()
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::error::Error;
+use crate::header::{Header, HeaderType};
+use crate::token::verified::VerifyWithKey;
+use crate::validation::{ClaimsValidator, Validation};
+use crate::{FromBase64, SignWithKey, Token};
+
+/// A party acting on behalf of the subject of a token, with the actors
+/// before it nested in `act`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    #[serde(rename = "sub")]
+    pub subject: String,
+
+    #[serde(rename = "iss", skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    #[serde(rename = "act", skip_serializing_if = "Option::is_none")]
+    pub actor: Option<Box<Actor>>,
+}
+
+impl Actor {
+    pub fn new(subject: impl Into<String>) -> Self {
+        Actor {
+            subject: subject.into(),
+            issuer: None,
+            client_id: None,
+            actor: None,
+        }
+    }
+
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// This actor followed by the actors before it, most recent first.
+    pub fn chain(&self) -> impl Iterator<Item = &Actor> {
+        core::iter::successors(Some(self), |actor| actor.actor.as_deref())
+    }
+
+    /// Whether this actor is the party identified by `other`, comparing the
+    /// issuer only if `other` names one.
+    pub fn is(&self, other: &Actor) -> bool {
+        self.subject == other.subject && (other.issuer.is_none() || self.issuer == other.issuer)
+    }
+}
+
+/// The claims of a token issued by a token exchange, as defined by
+/// [RFC 8693](https://tools.ietf.org/html/rfc8693#section-4).
+/// ## Examples
+/// ```
+/// use jwt::token::exchange::{Actor, DelegationValidation, ExchangeClaims};
+/// use jwt::FromBase64;
+///
+/// // {"sub":"someone","act":{"sub":"gateway","act":{"sub":"frontend"}},"scope":"read"}
+/// let claims = ExchangeClaims::from_base64(
+///     "eyJzdWIiOiJzb21lb25lIiwiYWN0Ijp7InN1YiI6ImdhdGV3YXkiLCJhY3QiOnsic3ViIjoiZnJvbnRlbmQifX0sInNjb3BlIjoicmVhZCJ9",
+/// )
+/// .unwrap();
+/// assert_eq!(claims.delegation_depth(), 2);
+///
+/// let validation = DelegationValidation::new()
+///     .max_depth(2)
+///     .permit(Actor::new("gateway"))
+///     .permit(Actor::new("frontend"));
+/// assert!(validation.check(&claims).is_ok());
+/// assert!(validation.max_depth(1).check(&claims).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeClaims {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+
+    /// The current actor, with the actors before it nested inside.
+    #[serde(rename = "act", skip_serializing_if = "Option::is_none")]
+    pub actor: Option<Actor>,
+
+    /// The party allowed to act on behalf of the subject.
+    #[serde(rename = "may_act", skip_serializing_if = "Option::is_none")]
+    pub may_act: Option<Actor>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    #[serde(flatten)]
+    pub private: BTreeMap<String, serde_json::Value>,
+}
+
+impl ExchangeClaims {
+    /// The number of actors in the `act` chain.
+    pub fn delegation_depth(&self) -> usize {
+        self.actor.as_ref().map_or(0, |actor| actor.chain().count())
+    }
+
+    /// Check that `actor` may act on behalf of the subject according to the
+    /// `may_act` claim, e.g. before exchanging this token on its behalf.
+    pub fn check_may_act(&self, actor: &Actor) -> Result<(), Error> {
+        match self.may_act {
+            Some(ref may_act) if actor.is(may_act) => Ok(()),
+            Some(_) => Err(Error::InvalidClaim("may_act".into())),
+            None => Err(Error::MissingClaim("may_act".into())),
+        }
+    }
+}
+
+impl AsRef<RegisteredClaims> for ExchangeClaims {
+    fn as_ref(&self) -> &RegisteredClaims {
+        &self.registered
+    }
+}
+
+/// Limits on the chain of actors of a token. By default, any actors are
+/// permitted in a chain of any length.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelegationValidation {
+    max_depth: Option<usize>,
+    permitted: Vec<Actor>,
+}
+
+impl DelegationValidation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject tokens delegated through more than `depth` actors.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Permit `actor` in the chain. When any actors are permitted, every
+    /// actor in the chain must be one of them.
+    pub fn permit(mut self, actor: Actor) -> Self {
+        self.permitted.push(actor);
+        self
+    }
+
+    pub fn check(&self, claims: &ExchangeClaims) -> Result<(), Error> {
+        if let Some(max_depth) = self.max_depth {
+            if claims.delegation_depth() > max_depth {
+                return Err(Error::InvalidClaim("act".into()));
+            }
+        }
+
+        if !self.permitted.is_empty() {
+            for actor in claims.actor.iter().flat_map(Actor::chain) {
+                if !self.permitted.iter().any(|permitted| actor.is(permitted)) {
+                    return Err(Error::InvalidClaim("act".into()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<H> ClaimsValidator<ExchangeClaims, H> for DelegationValidation {
+    fn validate(&self, _header: &H, claims: &ExchangeClaims) -> Result<(), Error> {
+        self.check(claims)
+    }
+}
+
+/// Outbound claims `C`, with the chain of actors that the token was
+/// exchanged by.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Exchanged<C> {
+    #[serde(flatten)]
+    pub claims: C,
+
+    #[serde(rename = "act", skip_serializing_if = "Option::is_none")]
+    pub actor: Option<Actor>,
+}
+
+#[derive(Deserialize)]
+struct ActorClaim {
+    #[serde(rename = "act")]
+    actor: Option<Actor>,
+}
+
+type Mapping<Cin, Cout> = Box<dyn Fn(Cin) -> Result<Cout, Error> + Send + Sync>;
+
+/// Verifies inbound tokens with one key, maps their claims, and signs the
+/// result with another. Outbound claims must serialize to a JSON object.
+pub struct TokenExchanger<Cin, Cout, V, K> {
+    verifier: V,
+    validation: Validation,
+    signer: K,
+    key_id: Option<String>,
+    actor: Option<Actor>,
+    map: Mapping<Cin, Cout>,
+}
+
+impl<Cin, Cout, V, K> TokenExchanger<Cin, Cout, V, K>
+where
+    Cin: FromBase64 + AsRef<RegisteredClaims>,
+    Cout: Serialize,
+    V: VerifyingAlgorithm,
+    K: SigningAlgorithm,
+{
+    pub fn new(
+        verifier: V,
+        validation: Validation,
+        signer: K,
+        map: impl Fn(Cin) -> Result<Cout, Error> + Send + Sync + 'static,
+    ) -> Self {
+        TokenExchanger {
+            verifier,
+            validation,
+            signer,
+            key_id: None,
+            actor: None,
+            map: Box::new(map),
+        }
+    }
+
+    /// The key id to put in the header of outbound tokens.
+    pub fn key_id(mut self, key_id: impl Into<String>) -> Self {
+        self.key_id = Some(key_id.into());
+        self
+    }
+
+    /// Add `actor` to the front of the `act` chain of outbound tokens,
+    /// before any actors of the inbound token.
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Exchange a token, validated against the current time of the platform
+    /// clock.
+    #[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+    pub fn exchange(&self, token_str: &str) -> Result<String, Error> {
+        self.exchange_at(token_str, crate::clock::now())
+    }
+
+    /// Exchange a token, validated against `now`, in seconds since the
+    /// epoch.
+    pub fn exchange_at(&self, token_str: &str, now: SecondsSinceEpoch) -> Result<String, Error> {
+        self.validation.check_token_str(token_str)?;
+        let inbound: Token<Header, Cin, _> = token_str.verify_with_key(&self.verifier)?;
+        self.validation
+            .validate_token_at(inbound.header(), inbound.claims(), now)?;
+
+        let actor = match self.actor {
+            Some(ref actor) => {
+                // The claims were verified above, so the `act` claim can be
+                // read from the same segment.
+                let [_, claims_str, _] = crate::token::verified::split_components(token_str)?;
+                let previous = ActorClaim::from_base64(claims_str)?.actor;
+                let mut actor = actor.clone();
+                actor.actor = previous.map(Box::new);
+                Some(actor)
+            }
+            None => None,
+        };
+
+        let (_, claims) = inbound.into();
+        let mut claims = match serde_json::to_value((self.map)(claims)?)? {
+            serde_json::Value::Object(claims) => claims,
+            _ => return Err(Error::Format),
+        };
+        // Replaces any `act` claim carried over by the mapping.
+        if let Some(actor) = actor {
+            claims.insert("act".into(), serde_json::to_value(actor)?);
+        }
+        let header = Header {
+            algorithm: self.signer.algorithm_type(),
+            key_id: self.key_id.clone(),
+            type_: Some(HeaderType::JsonWebToken),
+            content_type: None,
+        };
+
+        Ok(Token::new(header, claims)
+            .sign_with_key(&self.signer)?
+            .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::{Sha256, Sha384};
+
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::exchange::{Actor, Exchanged, TokenExchanger};
+    use crate::token::signed::SignWithKey;
+    use crate::token::verified::VerifyWithKey;
+    use crate::validation::Validation;
+    use crate::Token;
+
+    fn exchanger(
+        inbound_key: &Hmac<Sha256>,
+        outbound_key: &Hmac<Sha384>,
+        actor: &str,
+    ) -> TokenExchanger<Claims, Claims, Hmac<Sha256>, Hmac<Sha384>> {
+        TokenExchanger::new(
+            inbound_key.clone(),
+            Validation::new(),
+            outbound_key.clone(),
+            |mut claims: Claims| {
+                claims.registered.issuer = Some("sts".into());
+                Ok(claims)
+            },
+        )
+        .key_id("sts_key")
+        .actor(Actor::new(actor))
+    }
+
+    #[test]
+    pub fn chain_actors() -> Result<(), Error> {
+        let inbound_key: Hmac<Sha256> = Hmac::new_from_slice(b"inbound")?;
+        let outbound_key: Hmac<Sha384> = Hmac::new_from_slice(b"outbound")?;
+
+        let mut claims = Claims::new(RegisteredClaims {
+            subject: Some("someone".into()),
+            expiration: Some(2000),
+            ..Default::default()
+        });
+        claims.set_private("act", Actor::new("first"))?;
+        let inbound = claims.sign_with_key(&inbound_key)?;
+
+        let outbound =
+            exchanger(&inbound_key, &outbound_key, "second").exchange_at(&inbound, 1000)?;
+        let token: Token<Header, Exchanged<Claims>, _> =
+            outbound.as_str().verify_with_key(&outbound_key)?;
+        assert_eq!(token.header().key_id.as_deref(), Some("sts_key"));
+
+        let claims = token.claims();
+        assert_eq!(claims.claims.registered.issuer.as_deref(), Some("sts"));
+        assert_eq!(claims.claims.registered.subject.as_deref(), Some("someone"));
+        let actor = claims.actor.as_ref().unwrap();
+        assert_eq!(actor.subject, "second");
+        assert_eq!(actor.actor.as_ref().unwrap().subject, "first");
+
+        match exchanger(&inbound_key, &outbound_key, "second").exchange_at(&inbound, 2000) {
+            Err(Error::Expired) => (),
+            other => panic!("Expired token should not be exchanged: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn exchange_claims_roundtrip() -> Result<(), Error> {
+        use crate::token::exchange::{DelegationValidation, ExchangeClaims};
+        use crate::{FromBase64, ToBase64};
+
+        let claims = ExchangeClaims {
+            registered: RegisteredClaims {
+                subject: Some("someone".into()),
+                ..Default::default()
+            },
+            actor: Some(Actor {
+                actor: Some(Box::new(Actor::new("frontend").issuer("idp"))),
+                ..Actor::new("gateway")
+            }),
+            may_act: Some(Actor::new("backend")),
+            client_id: Some("app".into()),
+            scope: Some("read".into()),
+            ..Default::default()
+        };
+        let parsed = ExchangeClaims::from_base64(&*claims.to_base64()?)?;
+        assert_eq!(parsed, claims);
+        assert!(parsed.private.is_empty());
+
+        parsed.check_may_act(&Actor::new("backend"))?;
+        match parsed.check_may_act(&Actor::new("gateway")) {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "may_act"),
+            other => panic!("Wrong actor should not be allowed: {:?}", other),
+        }
+
+        let validation = DelegationValidation::new()
+            .permit(Actor::new("gateway"))
+            .permit(Actor::new("frontend").issuer("idp"));
+        validation.check(&parsed)?;
+
+        match DelegationValidation::new()
+            .permit(Actor::new("gateway"))
+            .permit(Actor::new("frontend").issuer("other"))
+            .check(&parsed)
+        {
+            Err(Error::InvalidClaim(name)) => assert_eq!(name, "act"),
+            other => panic!("Unpermitted actor should not be accepted: {:?}", other),
+        }
+        Ok(())
+    }
+}
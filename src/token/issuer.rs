@@ -0,0 +1,204 @@
+//! Verification of tokens from several issuers, each with its own keys and
+//! validation.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::token::issuer::MultiIssuerVerifier;
+//! use jwt::validation::Validation;
+//! use jwt::{Claims, Header, RegisteredClaims, SignWithStore, Token};
+//! use sha2::Sha256;
+//! use std::collections::BTreeMap;
+//!
+//! let mut first_store: BTreeMap<_, Hmac<Sha256>> = BTreeMap::new();
+//! first_store.insert("key", Hmac::new_from_slice(b"first").unwrap());
+//! let mut second_store: BTreeMap<_, Hmac<Sha256>> = BTreeMap::new();
+//! second_store.insert("key", Hmac::new_from_slice(b"second").unwrap());
+//!
+//! let mut verifier = MultiIssuerVerifier::new();
+//! verifier.add_issuer("first.com", first_store, Validation::new());
+//! verifier.add_issuer("second.com", second_store.clone(), Validation::new().audience("api"));
+//!
+//! let claims = RegisteredClaims {
+//!     issuer: Some("second.com".into()),
+//!     audience: Some("api".into()),
+//!     ..Default::default()
+//! };
+//! let token_str = ("key", claims).sign_with_store(&second_store).unwrap();
+//!
+//! let token: Token<Header, Claims, _> = verifier.verify_at(&token_str, 0).unwrap();
+//! assert_eq!(token.claims().registered.issuer.as_deref(), Some("second.com"));
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::algorithm::store::Store;
+use crate::algorithm::VerifyingAlgorithm;
+use crate::claims::{RegisteredClaims, SecondsSinceEpoch};
+use crate::dangerous;
+use crate::error::Error;
+use crate::header::JoseHeader;
+use crate::token::verified::VerifyWithStore;
+use crate::token::Verified;
+use crate::validation::Validation;
+use crate::{FromBase64, Token};
+
+/// Routes tokens to a key store and validation according to their `iss`
+/// claim. The issuer is read before the signature is verified, but is only
+/// used to choose the store, and is checked again by validation afterwards.
+pub struct MultiIssuerVerifier<S> {
+    issuers: BTreeMap<String, (S, Validation)>,
+}
+
+impl<S> Default for MultiIssuerVerifier<S> {
+    fn default() -> Self {
+        MultiIssuerVerifier {
+            issuers: BTreeMap::new(),
+        }
+    }
+}
+
+impl<S> MultiIssuerVerifier<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept tokens from `issuer`, verified with keys from `store`. The
+    /// validation is extended to require `iss` to equal `issuer`.
+    pub fn add_issuer(&mut self, issuer: impl Into<String>, store: S, validation: Validation) {
+        let issuer = issuer.into();
+        let validation = validation.issuer(issuer.clone());
+        self.issuers.insert(issuer, (store, validation));
+    }
+
+    pub fn remove_issuer(&mut self, issuer: &str) -> Option<(S, Validation)> {
+        self.issuers.remove(issuer)
+    }
+
+    /// Verify and validate a token against the current time of the platform
+    /// clock.
+    #[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+    pub fn verify<H, C, A>(&self, token_str: &str) -> Result<Token<H, C, Verified>, Error>
+    where
+        S: Store<Algorithm = A>,
+        A: VerifyingAlgorithm,
+        H: FromBase64 + JoseHeader,
+        C: FromBase64 + AsRef<RegisteredClaims>,
+    {
+        self.verify_at(token_str, crate::clock::now())
+    }
+
+    /// Verify and validate a token against `now`, in seconds since the epoch.
+    pub fn verify_at<H, C, A>(
+        &self,
+        token_str: &str,
+        now: SecondsSinceEpoch,
+    ) -> Result<Token<H, C, Verified>, Error>
+    where
+        S: Store<Algorithm = A>,
+        A: VerifyingAlgorithm,
+        H: FromBase64 + JoseHeader,
+        C: FromBase64 + AsRef<RegisteredClaims>,
+    {
+        let unverified: Token<H, C, _> = Token::parse_unverified(token_str)?;
+        let issuer = dangerous::unverified_claims(&unverified)
+            .as_ref()
+            .issuer
+            .as_deref()
+            .ok_or_else(|| Error::MissingClaim("iss".into()))?;
+        let (store, validation) = self
+            .issuers
+            .get(issuer)
+            .ok_or_else(|| Error::UnknownIssuer(issuer.to_owned()))?;
+        validation.check_token_str(token_str)?;
+
+        let verified = unverified.verify_with_store(store)?;
+        validation.validate_token_at(verified.header(), verified.claims(), now)?;
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::claims::RegisteredClaims;
+    use crate::error::Error;
+    use crate::header::Header;
+    use crate::token::issuer::MultiIssuerVerifier;
+    use crate::token::signed::SignWithStore;
+    use crate::validation::Validation;
+    use crate::Token;
+
+    type Store = BTreeMap<&'static str, Hmac<Sha256>>;
+
+    fn store(secret: &[u8]) -> Result<Store, Error> {
+        let mut store = BTreeMap::new();
+        store.insert("key", Hmac::new_from_slice(secret)?);
+        Ok(store)
+    }
+
+    fn verifier() -> Result<MultiIssuerVerifier<Store>, Error> {
+        let mut verifier = MultiIssuerVerifier::new();
+        verifier.add_issuer("first.com", store(b"first")?, Validation::new());
+        verifier.add_issuer("second.com", store(b"second")?, Validation::new());
+        Ok(verifier)
+    }
+
+    fn token(issuer: Option<&str>, secret: &[u8]) -> Result<String, Error> {
+        let claims = RegisteredClaims {
+            issuer: issuer.map(Into::into),
+            expiration: Some(2000),
+            ..Default::default()
+        };
+        ("key", claims).sign_with_store(&store(secret)?)
+    }
+
+    #[test]
+    pub fn routes_by_issuer() -> Result<(), Error> {
+        let verifier = verifier()?;
+
+        let first = token(Some("first.com"), b"first")?;
+        let token: Token<Header, RegisteredClaims, _> = verifier.verify_at(&first, 1000)?;
+        assert_eq!(token.claims().issuer.as_deref(), Some("first.com"));
+
+        match verifier.verify_at::<Header, RegisteredClaims, _>(&first, 2000) {
+            Err(Error::Expired) => (),
+            other => panic!("Expired token should not be accepted: {:?}", other.err()),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn rejects_mismatched_issuers() -> Result<(), Error> {
+        let verifier = verifier()?;
+
+        let forged = token(Some("first.com"), b"second")?;
+        match verifier.verify_at::<Header, RegisteredClaims, _>(&forged, 1000) {
+            Err(Error::RustCryptoMac(_)) => (),
+            other => panic!("Forged token should not be accepted: {:?}", other.err()),
+        }
+
+        let unknown = token(Some("third.com"), b"first")?;
+        match verifier.verify_at::<Header, RegisteredClaims, _>(&unknown, 1000) {
+            Err(Error::UnknownIssuer(issuer)) => assert_eq!(issuer, "third.com"),
+            other => panic!("Unknown issuer should not be accepted: {:?}", other.err()),
+        }
+
+        let anonymous = token(None, b"first")?;
+        match verifier.verify_at::<Header, RegisteredClaims, _>(&anonymous, 1000) {
+            Err(Error::MissingClaim(claim)) => assert_eq!(claim, "iss"),
+            other => panic!(
+                "Token without issuer should not be accepted: {:?}",
+                other.err()
+            ),
+        }
+
+        Ok(())
+    }
+}
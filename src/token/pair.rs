@@ -0,0 +1,267 @@
+//! Issue an access token together with a longer lived refresh token, and
+//! exchange the refresh token for new access tokens. Both tokens share a
+//! `jti`, identifying the lineage of tokens issued from the same login, e.g.
+//! to revoke them together. Access tokens have a `typ` of `at+jwt`, and
+//! refresh tokens of `refresh+jwt`. `refresh` only accepts refresh tokens,
+//! and `verify_access` only access tokens. Verifying an access token any
+//! other way, e.g. with `verify_with_key`, does not check `typ`, so a refresh
+//! token would pass as an access token unless the expected `typ` is set with
+//! [Validation::type_](../../validation/struct.Validation.html#method.type_).
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::token::pair;
+//! use jwt::{Claims, RegisteredClaims};
+//! use sha2::Sha256;
+//!
+//! # use jwt::Error;
+//! # fn try_main() -> Result<(), Error> {
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret")?;
+//! let claims = Claims::new(RegisteredClaims {
+//!     subject: Some("someone".into()),
+//!     ..Default::default()
+//! });
+//! let tokens = pair::issue(claims, 15 * 60, 30 * 24 * 60 * 60, &key)?;
+//!
+//! let access_str = pair::refresh(&tokens.refresh, 15 * 60, &key)?;
+//! let access: Claims = pair::verify_access(&access_str, &key)?;
+//! assert_eq!(access.registered.subject.as_deref(), Some("someone"));
+//! assert!(pair::verify_access(&tokens.refresh, &key).is_err());
+//! # Ok(())
+//! # }
+//! # try_main().unwrap()
+//! ```
+
+use alloc::string::String;
+
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+use crate::claims::{Claims, SecondsSinceEpoch};
+use crate::encoding;
+use crate::error::Error;
+use crate::header::{Header, HeaderType};
+use crate::validation::Validation;
+use crate::{SignWithKey, Token, VerifyWithKey};
+
+/// The `typ` header of refresh tokens.
+pub const REFRESH_TYPE: &str = "refresh+jwt";
+
+/// Bytes of randomness in a generated lineage id.
+const LINEAGE_LEN: usize = 16;
+
+/// A newly issued access token and its refresh token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenPair {
+    pub access: String,
+    pub refresh: String,
+}
+
+/// Issue an access token and a refresh token for `claims`, valid for the
+/// given number of seconds. The `jti` of `claims` is used as the lineage of
+/// the tokens, or a random one is generated if it is missing.
+pub fn issue(
+    claims: Claims,
+    access_ttl: SecondsSinceEpoch,
+    refresh_ttl: SecondsSinceEpoch,
+    key: &impl SigningAlgorithm,
+) -> Result<TokenPair, Error> {
+    issue_at(claims, access_ttl, refresh_ttl, key, crate::clock::now())
+}
+
+/// Issue a token pair at the given time.
+pub fn issue_at(
+    mut claims: Claims,
+    access_ttl: SecondsSinceEpoch,
+    refresh_ttl: SecondsSinceEpoch,
+    key: &impl SigningAlgorithm,
+    now: SecondsSinceEpoch,
+) -> Result<TokenPair, Error> {
+    if claims.registered.json_web_token_id.is_none() {
+        claims.registered.json_web_token_id = Some(new_lineage()?);
+    }
+    claims.registered.issued_at = Some(now);
+
+    claims.registered.expiration = Some(now.saturating_add(refresh_ttl));
+    let header = Header {
+        algorithm: key.algorithm_type(),
+        type_: Some(HeaderType::Custom(REFRESH_TYPE.into())),
+        ..Default::default()
+    };
+    let refresh = Token::new(header, &claims).sign_with_key(key)?.into();
+
+    Ok(TokenPair {
+        access: sign_access(claims, access_ttl, key, now)?,
+        refresh,
+    })
+}
+
+/// Exchange a refresh token for a new access token with the same claims and
+/// lineage, valid for `access_ttl` seconds.
+pub fn refresh<K>(
+    refresh_str: &str,
+    access_ttl: SecondsSinceEpoch,
+    key: &K,
+) -> Result<String, Error>
+where
+    K: SigningAlgorithm + VerifyingAlgorithm,
+{
+    refresh_at(refresh_str, access_ttl, key, crate::clock::now())
+}
+
+/// Exchange a refresh token at the given time.
+pub fn refresh_at<K>(
+    refresh_str: &str,
+    access_ttl: SecondsSinceEpoch,
+    key: &K,
+    now: SecondsSinceEpoch,
+) -> Result<String, Error>
+where
+    K: SigningAlgorithm + VerifyingAlgorithm,
+{
+    let token: Token<Header, Claims, _> = refresh_str.verify_with_key(key)?;
+    let validation = Validation::new()
+        .type_(HeaderType::Custom(REFRESH_TYPE.into()))
+        .require("exp")
+        .require("jti");
+    validation.validate_token_at(token.header(), token.claims(), now)?;
+    let (_, claims) = token.into();
+
+    sign_access(claims, access_ttl, key, now)
+}
+
+/// Verify an access token and validate its claims, rejecting refresh tokens
+/// and any other token without a `typ` of `at+jwt`.
+pub fn verify_access(access_str: &str, key: &impl VerifyingAlgorithm) -> Result<Claims, Error> {
+    verify_access_at(access_str, key, crate::clock::now())
+}
+
+/// Verify an access token at the given time.
+pub fn verify_access_at(
+    access_str: &str,
+    key: &impl VerifyingAlgorithm,
+    now: SecondsSinceEpoch,
+) -> Result<Claims, Error> {
+    let token: Token<Header, Claims, _> = access_str.verify_with_key(key)?;
+    let validation = Validation::new()
+        .type_(HeaderType::AccessToken)
+        .require("exp");
+    validation.validate_token_at(token.header(), token.claims(), now)?;
+    let (_, claims) = token.into();
+    Ok(claims)
+}
+
+fn sign_access(
+    mut claims: Claims,
+    access_ttl: SecondsSinceEpoch,
+    key: &impl SigningAlgorithm,
+    now: SecondsSinceEpoch,
+) -> Result<String, Error> {
+    claims.registered.issued_at = Some(now);
+    claims.registered.expiration = Some(now.saturating_add(access_ttl));
+    let header = Header {
+        algorithm: key.algorithm_type(),
+        type_: Some(HeaderType::AccessToken),
+        ..Default::default()
+    };
+    Ok(Token::new(header, claims).sign_with_key(key)?.into())
+}
+
+fn new_lineage() -> Result<String, Error> {
+    let mut bytes = [0; LINEAGE_LEN];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(encoding::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::claims::{Claims, RegisteredClaims};
+    use crate::error::Error;
+    use crate::header::{Header, HeaderType};
+    use crate::token::pair::{issue_at, refresh_at, verify_access_at, REFRESH_TYPE};
+    use crate::token::verified::VerifyWithKey;
+    use crate::Token;
+
+    fn claims() -> Claims {
+        Claims::new(RegisteredClaims {
+            subject: Some("someone".into()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    pub fn issue_linked_tokens() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let tokens = issue_at(claims(), 10, 100, &key, 1000)?;
+
+        let access: Claims = tokens.access.as_str().verify_with_key(&key)?;
+        let refresh: Token<Header, Claims, _> = tokens.refresh.as_str().verify_with_key(&key)?;
+        assert_eq!(
+            refresh.header().type_,
+            Some(HeaderType::Custom(REFRESH_TYPE.into()))
+        );
+        let refresh = refresh.claims();
+        assert_eq!(access.registered.expiration, Some(1010));
+        assert_eq!(refresh.registered.expiration, Some(1100));
+        assert!(access.registered.json_web_token_id.is_some());
+        assert_eq!(
+            access.registered.json_web_token_id,
+            refresh.registered.json_web_token_id
+        );
+
+        let other = issue_at(claims(), 10, 100, &key, 1000)?;
+        assert_ne!(other.refresh, tokens.refresh);
+        Ok(())
+    }
+
+    #[test]
+    pub fn refresh_access_token() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let tokens = issue_at(claims(), 10, 100, &key, 1000)?;
+
+        let access_str = refresh_at(&tokens.refresh, 10, &key, 1050)?;
+        let access: Claims = access_str.as_str().verify_with_key(&key)?;
+        let original: Claims = tokens.access.as_str().verify_with_key(&key)?;
+        assert_eq!(access.registered.subject.as_deref(), Some("someone"));
+        assert_eq!(access.registered.expiration, Some(1060));
+        assert_eq!(
+            access.registered.json_web_token_id,
+            original.registered.json_web_token_id
+        );
+
+        match refresh_at(&tokens.refresh, 10, &key, 1100) {
+            Err(Error::Expired) => (),
+            other => panic!("Wrong result: {:?}", other),
+        }
+
+        match refresh_at(&tokens.access, 10, &key, 1000) {
+            Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "typ"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn verify_access_token() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let tokens = issue_at(claims(), 10, 100, &key, 1000)?;
+
+        let access = verify_access_at(&tokens.access, &key, 1005)?;
+        assert_eq!(access.registered.subject.as_deref(), Some("someone"));
+
+        // A refresh token passes plain verification, but not as an access
+        // token.
+        let _: Claims = tokens.refresh.as_str().verify_with_key(&key)?;
+        match verify_access_at(&tokens.refresh, &key, 1005) {
+            Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "typ"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+
+        match verify_access_at(&tokens.access, &key, 1010) {
+            Err(Error::Expired) => (),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+}
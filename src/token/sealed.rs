@@ -0,0 +1,461 @@
+//! Encrypted tokens for session cookies, sealed with a single symmetric key.
+//! Claims are encrypted and authenticated with AES-256-GCM, so they can be
+//! neither read nor modified by the client, and expire after a maximum age.
+//! Keys are versioned, so that a new key can be introduced while tokens
+//! sealed with previous keys remain valid until they expire. Requires the
+//! `sealed` feature.
+//! ## Examples
+//! ```
+//! use jwt::token::sealed::{SealedToken, SealingKey};
+//! use std::collections::BTreeMap;
+//!
+//! # use jwt::Error;
+//! # fn try_main() -> Result<(), Error> {
+//! let old_key = SealingKey::new(1, &[1; 32])?;
+//! let mut claims = BTreeMap::new();
+//! claims.insert("sub".to_owned(), "someone".to_owned());
+//! let cookie = SealedToken::seal(&claims, &old_key)?;
+//!
+//! let key = SealingKey::new(2, &[2; 32])?
+//!     .previous(1, &[1; 32])?
+//!     .max_age(60 * 60);
+//! let token: SealedToken<BTreeMap<String, String>> = SealedToken::open(&cookie, &key)?;
+//! assert_eq!(token.claims()["sub"], "someone");
+//! # Ok(())
+//! # }
+//! # try_main().unwrap()
+//! ```
+//!
+//! A single claim can also be encrypted with the same keys, inside an
+//! otherwise normal signed token, by wrapping it in `Protected`. Every party
+//! that sees the token can read its other claims, but only holders of the key
+//! can read the protected ones. Sealing with the name of the claim keeps the
+//! value from being moved to another protected claim.
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::token::sealed::{Protected, SealingKey};
+//! use jwt::{SignWithKey, VerifyWithKey};
+//! use serde::{Deserialize, Serialize};
+//! use sha2::Sha256;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//!     email: Protected<String>,
+//! }
+//!
+//! # use jwt::Error;
+//! # fn try_main() -> Result<(), Error> {
+//! let signing_key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret")?;
+//! let sealing_key = SealingKey::new(1, &[1; 32])?;
+//!
+//! let claims = Claims {
+//!     sub: "someone".into(),
+//!     email: Protected::seal_as(&"someone@example.com".to_string(), &sealing_key, "email")?,
+//! };
+//! let token_str = claims.sign_with_key(&signing_key)?;
+//!
+//! let claims: Claims = token_str.verify_with_key(&signing_key)?;
+//! assert_eq!(claims.email.open_as(&sealing_key, "email")?, "someone@example.com");
+//! # Ok(())
+//! # }
+//! # try_main().unwrap()
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::claims::SecondsSinceEpoch;
+use crate::encoding;
+use crate::error::Error;
+use crate::SEPARATOR;
+
+/// Default maximum age of a sealed token, one day.
+pub const DEFAULT_MAX_AGE: u64 = 24 * 60 * 60;
+
+const NONCE_LEN: usize = 12;
+
+/// Authenticated with each protected claim, so that it cannot be swapped with
+/// a sealed token or another ciphertext made with the same key.
+const PROTECTED_CLAIM_LABEL: &[u8] = b"jwt-protected-claim";
+
+/// Versioned AES-256-GCM keys. New tokens are sealed with the current key,
+/// and tokens sealed with any of the keys can be opened.
+pub struct SealingKey {
+    version: u32,
+    keys: BTreeMap<u32, Aes256Gcm>,
+    max_age: u64,
+}
+
+impl SealingKey {
+    /// A 32 byte key, used for sealing and opening tokens.
+    pub fn new(version: u32, key: &[u8]) -> Result<Self, Error> {
+        let mut keys = BTreeMap::new();
+        keys.insert(version, Aes256Gcm::new_from_slice(key)?);
+        Ok(SealingKey {
+            version,
+            keys,
+            max_age: DEFAULT_MAX_AGE,
+        })
+    }
+
+    /// A previous 32 byte key, only used for opening tokens.
+    pub fn previous(mut self, version: u32, key: &[u8]) -> Result<Self, Error> {
+        if version != self.version {
+            self.keys.insert(version, Aes256Gcm::new_from_slice(key)?);
+        }
+        Ok(self)
+    }
+
+    /// The number of seconds sealed tokens are valid for.
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = seconds;
+        self
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Encrypt with the current key, as the version and the base64 encoded
+    /// nonce and ciphertext. The version is authenticated after `context`.
+    fn encrypt(&self, plaintext: &[u8], context: &[u8]) -> Result<String, Error> {
+        let version = self.version.to_string();
+        let aad = [context, version.as_bytes()].concat();
+        let cipher = &self.keys[&self.version];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Format)?;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        let mut sealed_string = version;
+        sealed_string.push_str(SEPARATOR);
+        encoding::encode_into(sealed, &mut sealed_string);
+        Ok(sealed_string)
+    }
+
+    /// Decrypt with the version of the key the input was encrypted with, and
+    /// the `context` it was encrypted with.
+    fn decrypt(&self, sealed_str: &str, context: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut components = sealed_str.splitn(2, SEPARATOR);
+        let version_str = components.next().ok_or(Error::NoHeaderComponent)?;
+        let sealed_str = components.next().ok_or(Error::NoClaimsComponent)?;
+
+        let version: u32 = version_str.parse().map_err(|_| Error::Format)?;
+        let cipher = self
+            .keys
+            .get(&version)
+            .ok_or_else(|| Error::NoKeyWithKeyId(version_str.to_string()))?;
+
+        let sealed = encoding::decode(sealed_str)?;
+        if sealed.len() < NONCE_LEN {
+            return Err(Error::Format);
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let aad = [context, version_str.as_bytes()].concat();
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::InvalidSignature)
+    }
+}
+
+/// The claims of an opened token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SealedToken<C> {
+    #[serde(rename = "exp")]
+    expiration: SecondsSinceEpoch,
+    claims: C,
+}
+
+impl<C> SealedToken<C> {
+    pub fn claims(&self) -> &C {
+        &self.claims
+    }
+
+    pub fn into_claims(self) -> C {
+        self.claims
+    }
+
+    pub fn expiration(&self) -> SecondsSinceEpoch {
+        self.expiration
+    }
+}
+
+impl<C: Serialize> SealedToken<C> {
+    /// Seal claims with the current key, to expire after its maximum age.
+    pub fn seal(claims: &C, key: &SealingKey) -> Result<String, Error> {
+        Self::seal_at(claims, key, crate::clock::now())
+    }
+
+    /// Seal claims at the given time.
+    pub fn seal_at(claims: &C, key: &SealingKey, now: SecondsSinceEpoch) -> Result<String, Error> {
+        let token = SealedToken {
+            expiration: now.saturating_add(key.max_age),
+            claims,
+        };
+        key.encrypt(&serde_json::to_vec(&token)?, &[])
+    }
+}
+
+impl<C: DeserializeOwned> SealedToken<C> {
+    /// Open a token sealed with any version of the key, checking that it has
+    /// not expired.
+    pub fn open(token_str: &str, key: &SealingKey) -> Result<Self, Error> {
+        Self::open_at(token_str, key, crate::clock::now())
+    }
+
+    /// Open a token at the given time.
+    pub fn open_at(
+        token_str: &str,
+        key: &SealingKey,
+        now: SecondsSinceEpoch,
+    ) -> Result<Self, Error> {
+        let plaintext = key.decrypt(token_str, &[])?;
+        let token: SealedToken<C> = serde_json::from_slice(&plaintext)?;
+        if now >= token.expiration {
+            return Err(Error::Expired);
+        }
+        Ok(token)
+    }
+}
+
+/// A claim encrypted with a `SealingKey`, serialized as the version of the
+/// key and the base64 encoded ciphertext. The value is sealed when the claims
+/// are built and opened after the token is verified, with the key supplied at
+/// each step.
+///
+/// A protected claim can't be opened as a sealed token, or the other way
+/// around. Use `seal_as` and `open_as` to also bind it to the name of the
+/// claim, so that it can't be moved to another claim sealed with the same
+/// key.
+pub struct Protected<T> {
+    sealed: String,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize> Protected<T> {
+    /// Encrypt `value` with the current key.
+    pub fn seal(value: &T, key: &SealingKey) -> Result<Self, Error> {
+        Self::seal_as(value, key, "")
+    }
+
+    /// Encrypt `value` with the current key, for the claim `name`. It can
+    /// only be opened with `open_as` and the same name.
+    pub fn seal_as(value: &T, key: &SealingKey, name: &str) -> Result<Self, Error> {
+        Ok(Protected {
+            sealed: key.encrypt(&serde_json::to_vec(value)?, &context(name))?,
+            value: PhantomData,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> Protected<T> {
+    /// Decrypt the value with any version of the key.
+    pub fn open(&self, key: &SealingKey) -> Result<T, Error> {
+        self.open_as(key, "")
+    }
+
+    /// Decrypt a value sealed for the claim `name`.
+    pub fn open_as(&self, key: &SealingKey, name: &str) -> Result<T, Error> {
+        let plaintext = key.decrypt(&self.sealed, &context(name))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+/// The label and claim name, each followed by a zero byte, that are
+/// authenticated before the key version. The version never contains a zero
+/// byte, so the last one always ends the name.
+fn context(name: &str) -> Vec<u8> {
+    [PROTECTED_CLAIM_LABEL, b"\0", name.as_bytes(), b"\0"].concat()
+}
+
+impl<T> Protected<T> {
+    /// The serialized form of the claim.
+    pub fn as_str(&self) -> &str {
+        &self.sealed
+    }
+}
+
+impl<T> Clone for Protected<T> {
+    fn clone(&self) -> Self {
+        Protected {
+            sealed: self.sealed.clone(),
+            value: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Protected<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sealed == other.sealed
+    }
+}
+
+impl<T> Eq for Protected<T> {}
+
+impl<T> fmt::Debug for Protected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Protected").field(&self.sealed).finish()
+    }
+}
+
+impl<T> Serialize for Protected<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.sealed)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Protected<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Protected {
+            sealed: String::deserialize(deserializer)?,
+            value: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::error::Error;
+    use crate::token::sealed::{Protected, SealedToken, SealingKey};
+
+    type Claims = BTreeMap<String, String>;
+
+    fn claims() -> Claims {
+        let mut claims = BTreeMap::new();
+        claims.insert("sub".to_owned(), "someone".to_owned());
+        claims
+    }
+
+    #[test]
+    pub fn seal_and_open() -> Result<(), Error> {
+        let key = SealingKey::new(1, &[7; 32])?.max_age(100);
+        let sealed = SealedToken::seal_at(&claims(), &key, 1000)?;
+        assert!(sealed.starts_with("1."));
+        assert!(!sealed.contains("someone"));
+
+        let token: SealedToken<Claims> = SealedToken::open_at(&sealed, &key, 1099)?;
+        assert_eq!(token.claims()["sub"], "someone");
+        assert_eq!(token.expiration(), 1100);
+
+        match SealedToken::<Claims>::open_at(&sealed, &key, 1100) {
+            Err(Error::Expired) => (),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn rotate_keys() -> Result<(), Error> {
+        let old_key = SealingKey::new(1, &[1; 32])?;
+        let old_sealed = SealedToken::seal_at(&claims(), &old_key, 1000)?;
+
+        let key = SealingKey::new(2, &[2; 32])?.previous(1, &[1; 32])?;
+        let sealed = SealedToken::seal_at(&claims(), &key, 1000)?;
+        assert!(sealed.starts_with("2."));
+        SealedToken::<Claims>::open_at(&old_sealed, &key, 1000)?;
+
+        match SealedToken::<Claims>::open_at(&sealed, &old_key, 1000) {
+            Err(Error::NoKeyWithKeyId(version)) => assert_eq!(version, "2"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+
+        let forged = sealed.replacen('2', "1", 1);
+        match SealedToken::<Claims>::open_at(&forged, &key, 1000) {
+            Err(Error::InvalidSignature) => (),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn protected_claims() -> Result<(), Error> {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        use crate::token::signed::SignWithKey;
+        use crate::token::verified::VerifyWithKey;
+
+        let signing_key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let key = SealingKey::new(2, &[2; 32])?.previous(1, &[1; 32])?;
+        let old_key = SealingKey::new(1, &[1; 32])?;
+
+        let email = "someone@example.com".to_owned();
+        let mut claims = BTreeMap::new();
+        claims.insert("sub", Protected::seal(&"someone".to_owned(), &old_key)?);
+        claims.insert("email", Protected::seal(&email, &key)?);
+        assert!(claims["email"].as_str().starts_with("2."));
+        assert_ne!(claims["email"], Protected::seal(&email, &key)?);
+
+        let token_str = claims.sign_with_key(&signing_key)?;
+        let verified: BTreeMap<String, Protected<String>> =
+            token_str.verify_with_key(&signing_key)?;
+        assert_eq!(verified["email"].open(&key)?, email);
+        assert_eq!(verified["sub"].open(&key)?, "someone");
+
+        match verified["email"].open(&old_key) {
+            Err(Error::NoKeyWithKeyId(version)) => assert_eq!(version, "2"),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        let wrong_key = SealingKey::new(2, &[3; 32])?;
+        assert!(matches!(
+            verified["email"].open(&wrong_key),
+            Err(Error::InvalidSignature)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn protected_contexts() -> Result<(), Error> {
+        let key = SealingKey::new(1, &[1; 32])?;
+        let email = "someone@example.com".to_owned();
+
+        let protected = Protected::seal_as(&email, &key, "email")?;
+        assert_eq!(protected.open_as(&key, "email")?, email);
+        for name in &["sub", ""] {
+            match protected.open_as(&key, name) {
+                Err(Error::InvalidSignature) => (),
+                other => panic!("Wrong result: {:?}", other),
+            }
+        }
+        assert!(matches!(protected.open(&key), Err(Error::InvalidSignature)));
+
+        // A sealed token can't be passed off as a protected claim.
+        let sealed = SealedToken::seal_at(&email, &key, 1000)?;
+        let protected: Protected<String> = serde_json::from_value(sealed.into())?;
+        assert!(matches!(protected.open(&key), Err(Error::InvalidSignature)));
+
+        let protected = Protected::seal(&email, &key)?;
+        match SealedToken::<String>::open_at(protected.as_str(), &key, 1000) {
+            Err(Error::InvalidSignature) => (),
+            other => panic!("Wrong result: {:?}", other),
+        }
+        Ok(())
+    }
+}
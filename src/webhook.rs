@@ -0,0 +1,223 @@
+//! Signatures of webhook requests. The sender signs the method, path, and a
+//! SHA-256 digest of the body of a request, along with the time it was sent,
+//! and sends the token alongside the request, e.g. in a header. The receiver
+//! verifies the token against the request it received, rejecting requests
+//! that were altered or are replayed outside of a tolerance window.
+//!
+//! A request can still be replayed within the window. Receivers that need
+//! each request to be accepted only once should also remember the tokens
+//! they have accepted until the window has passed.
+//! ## Examples
+//! ```
+//! use hmac::{Hmac, Mac};
+//! use jwt::webhook::{WebhookRequest, WebhookVerifier};
+//! use sha2::Sha256;
+//!
+//! let key: Hmac<Sha256> = Hmac::new_from_slice(b"some-secret").unwrap();
+//! let request = WebhookRequest::new("POST", "/hooks/orders", b"{\"order\":1}");
+//! let signature = request.sign_at(&key, 1302319000).unwrap();
+//!
+//! let verifier = WebhookVerifier::new().tolerance(60);
+//! assert!(verifier.verify_at(&signature, &request, &key, 1302319030).is_ok());
+//! assert!(verifier.verify_at(&signature, &request, &key, 1302319100).is_err());
+//!
+//! let altered = WebhookRequest::new("POST", "/hooks/orders", b"{\"order\":2}");
+//! assert!(verifier.verify_at(&signature, &altered, &key, 1302319030).is_err());
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::algorithm::{SigningAlgorithm, VerifyingAlgorithm};
+use crate::claims::SecondsSinceEpoch;
+use crate::encoding;
+use crate::error::Error;
+use crate::token::signed::SignWithKey;
+use crate::token::verified::VerifyWithKey;
+
+/// The parts of an HTTP request covered by its signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebhookRequest<'a> {
+    /// The request method, compared case-insensitively.
+    pub method: &'a str,
+    /// The path of the request, including any query string.
+    pub path: &'a str,
+    pub body: &'a [u8],
+}
+
+/// The claims signed for a webhook request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookClaims {
+    pub method: String,
+    pub path: String,
+    #[serde(rename = "iat")]
+    pub issued_at: SecondsSinceEpoch,
+    /// The SHA-256 digest of the body, base64 encoded.
+    pub body_sha256: String,
+}
+
+impl<'a> WebhookRequest<'a> {
+    pub fn new(method: &'a str, path: &'a str, body: &'a [u8]) -> Self {
+        WebhookRequest { method, path, body }
+    }
+
+    /// The claims of the request, sent at `timestamp`.
+    pub fn claims(&self, timestamp: SecondsSinceEpoch) -> WebhookClaims {
+        WebhookClaims {
+            method: self.method.to_ascii_uppercase(),
+            path: self.path.to_owned(),
+            issued_at: timestamp,
+            body_sha256: self.body_sha256(),
+        }
+    }
+
+    /// Sign the request as sent now.
+    #[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+    pub fn sign(&self, key: &impl SigningAlgorithm) -> Result<String, Error> {
+        self.sign_at(key, crate::clock::now())
+    }
+
+    /// Sign the request as sent at `timestamp`.
+    pub fn sign_at(
+        &self,
+        key: &impl SigningAlgorithm,
+        timestamp: SecondsSinceEpoch,
+    ) -> Result<String, Error> {
+        self.claims(timestamp).sign_with_key(key)
+    }
+
+    fn body_sha256(&self) -> String {
+        encoding::encode(Sha256::digest(self.body))
+    }
+}
+
+/// Verifies webhook signatures, accepting requests sent within `tolerance`
+/// seconds of the time they are received. The tolerance defaults to five
+/// minutes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookVerifier {
+    tolerance: SecondsSinceEpoch,
+}
+
+impl Default for WebhookVerifier {
+    fn default() -> Self {
+        WebhookVerifier { tolerance: 300 }
+    }
+}
+
+impl WebhookVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tolerance(mut self, tolerance: SecondsSinceEpoch) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Verify the signature of a request received now.
+    #[cfg(any(feature = "std", all(feature = "js", target_arch = "wasm32")))]
+    pub fn verify(
+        &self,
+        signature: &str,
+        request: &WebhookRequest,
+        key: &impl VerifyingAlgorithm,
+    ) -> Result<WebhookClaims, Error> {
+        self.verify_at(signature, request, key, crate::clock::now())
+    }
+
+    /// Verify the signature of a request received at `now`. A request sent
+    /// too long ago fails with `Error::Expired`, and one sent too far in the
+    /// future with `Error::NotYetValid`. A request that does not match the
+    /// signed claims fails with `Error::InvalidClaim`.
+    pub fn verify_at(
+        &self,
+        signature: &str,
+        request: &WebhookRequest,
+        key: &impl VerifyingAlgorithm,
+        now: SecondsSinceEpoch,
+    ) -> Result<WebhookClaims, Error> {
+        let claims: WebhookClaims = signature.verify_with_key(key)?;
+
+        if claims.issued_at.saturating_add(self.tolerance) < now {
+            return Err(Error::Expired);
+        }
+        if claims.issued_at > now.saturating_add(self.tolerance) {
+            return Err(Error::NotYetValid);
+        }
+
+        if !claims.method.eq_ignore_ascii_case(request.method) {
+            return Err(Error::InvalidClaim("method".into()));
+        }
+        if claims.path != request.path {
+            return Err(Error::InvalidClaim("path".into()));
+        }
+        if claims.body_sha256 != request.body_sha256() {
+            return Err(Error::InvalidClaim("body_sha256".into()));
+        }
+
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use crate::error::Error;
+    use crate::webhook::{WebhookRequest, WebhookVerifier};
+
+    #[test]
+    pub fn verify() -> Result<(), Error> {
+        let key: Hmac<Sha256> = Hmac::new_from_slice(b"secret")?;
+        let request = WebhookRequest::new("post", "/hooks?tenant=1", b"body");
+        let signature = request.sign_at(&key, 1000)?;
+        let verifier = WebhookVerifier::new().tolerance(10);
+
+        let claims = verifier.verify_at(&signature, &request, &key, 1010)?;
+        assert_eq!(claims.method, "POST");
+        assert_eq!(claims.issued_at, 1000);
+        let uppercase = WebhookRequest::new("POST", "/hooks?tenant=1", b"body");
+        verifier.verify_at(&signature, &uppercase, &key, 990)?;
+
+        assert!(matches!(
+            verifier.verify_at(&signature, &request, &key, 1011),
+            Err(Error::Expired)
+        ));
+        assert!(matches!(
+            verifier.verify_at(&signature, &request, &key, 989),
+            Err(Error::NotYetValid)
+        ));
+
+        let altered = [
+            (
+                WebhookRequest::new("PUT", "/hooks?tenant=1", b"body"),
+                "method",
+            ),
+            (
+                WebhookRequest::new("POST", "/hooks?tenant=2", b"body"),
+                "path",
+            ),
+            (
+                WebhookRequest::new("POST", "/hooks?tenant=1", b"body!"),
+                "body_sha256",
+            ),
+        ];
+        for (altered, claim) in altered.iter() {
+            match verifier.verify_at(&signature, altered, &key, 1000) {
+                Err(Error::InvalidClaim(name)) => assert_eq!(name, *claim),
+                other => panic!("Altered {} should not be accepted: {:?}", claim, other),
+            }
+        }
+
+        let wrong_key: Hmac<Sha256> = Hmac::new_from_slice(b"wrong")?;
+        assert!(verifier
+            .verify_at(&signature, &request, &wrong_key, 1000)
+            .is_err());
+        Ok(())
+    }
+}